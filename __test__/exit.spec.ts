@@ -0,0 +1,81 @@
+import { spawnSync } from 'node:child_process'
+
+import { type Context, defineCommand, run } from 'archons'
+
+class NotFoundError extends Error {}
+
+const main = defineCommand({
+  meta: {
+    name: 'test',
+    exitCodes: {
+      NotFoundError: 4,
+    },
+  },
+  options: {
+    code: {
+      type: 'option',
+      parser: 'number',
+    },
+  },
+  subcommands: {
+    find: defineCommand({
+      meta: {
+        exitCodes: {
+          ENOENT: 5,
+        },
+      },
+      options: {
+        missing: {
+          type: 'option',
+          parser: 'boolean',
+        },
+      },
+      callback: (ctx: Context) => {
+        if (ctx.args.missing) {
+          throw Object.assign(new Error('file not found'), { code: 'ENOENT' })
+        }
+        throw new NotFoundError('package not found')
+      },
+    }),
+    exit: defineCommand({
+      meta: {},
+      options: {},
+      hooks: {
+        postRun: () => {
+          throw new Error('unreachable')
+        },
+      },
+      callback: (ctx: Context) => {
+        ctx.exit(ctx.args.code)
+      },
+    }),
+  },
+  callback: (ctx: Context) => ctx.args.code,
+})
+
+afterEach(() => {
+  process.exitCode = 0
+})
+
+test('return exit code from callback', async () => {
+  expect(run(main, ['node', 'test.js'])).toBe(0)
+  expect(run(main, ['node', 'test.js', '--code', '3'])).toBe(3)
+  expect(run(main, ['node', 'test.js', '--code', '6', 'exit'])).toBe(6)
+  const asyncMain = defineCommand({
+    meta: {},
+    options: {},
+    callback: async () => 7,
+  })
+  expect(await run(asyncMain, ['node', 'test.js'])).toBe(7)
+})
+
+test('map errors to exit codes', () => {
+  expect(run(main, ['node', 'test.js', 'find'])).toBe(4)
+  expect(run(main, ['node', 'test.js', 'find', '--missing'])).toBe(5)
+})
+
+test('exit code of parsing errors', () => {
+  const result = spawnSync('node', ['examples/simple.cjs'])
+  expect(result.status).toBe(2)
+  expect(result.stderr.toString()).toContain('error: the following required arguments were not provided')
+})
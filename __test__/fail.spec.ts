@@ -0,0 +1,38 @@
+import { spawnSync } from 'node:child_process'
+import { type Context, defineCommand, runForTest } from 'archons'
+
+test('raise clap error from callback', () => {
+  const result = spawnSync('node', ['examples/fail.cjs', 'kind'])
+  expect(result.error).toBe(undefined)
+  expect(result.status).toBe(2)
+  expect(result.stdout.length).toBe(0)
+  expect(result.stderr.toString()).toBe(
+    "error: invalid manifest 'kind'\n\nUsage: fail.cjs [OPTIONS] <manifest>\n\nFor more information, try '--help'.\n",
+  )
+})
+
+test('fail with exit code and hint', () => {
+  const result = spawnSync('node', ['examples/fail.cjs', 'app.json', '--code', '3'])
+  expect(result.error).toBe(undefined)
+  expect(result.status).toBe(3)
+  const stderr = result.stderr.toString()
+  expect(stderr).toContain('error: manifest is not valid')
+  expect(stderr).toContain('tip: run `fail init` to create one')
+  expect(stderr).toContain('Usage: fail.cjs [OPTIONS] <manifest>')
+})
+
+test('reject unsupported error kind', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    callback: (ctx: Context) => {
+      // @ts-expect-error the kind is checked at runtime as well
+      ctx.error('bogus', 'invalid manifest')
+    },
+  })
+  const runBogus = () => runForTest(main, ['node', 'test.js'])
+  expect(runBogus).toThrow(TypeError)
+  expect(runBogus).toThrow('Unsupported error kind `bogus`, expected one of `invalid-value`, ')
+})
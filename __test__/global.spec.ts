@@ -24,7 +24,5 @@ test('global option', () => {
       dev,
     },
   })
-  expect(() => {
-    run(main, ['node', 'test.js', 'dev', '--config', 'config.json'])
-  }).not.toThrow()
+  expect(run(main, ['node', 'test.js', 'dev', '--config', 'config.json'])).toBe(0)
 })
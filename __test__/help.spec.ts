@@ -0,0 +1,126 @@
+import { type Context, defineCommand, run, runForTest } from 'archons'
+
+test('render help and usage from callback', () => {
+  const add = defineCommand({
+    meta: {
+      about: 'Add a remote',
+    },
+    options: {
+      url: {
+        type: 'positional',
+        help: 'Remote url',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.usage()).toBe('Usage: cli.js remote add [url]')
+      const help = ctx.help()
+      expect(help).toContain('Add a remote')
+      expect(help).toContain('Usage: cli.js remote add [url]')
+      expect(help).toContain('[url]  Remote url')
+      expect(help).toContain('-h, --help')
+      expect(ctx.renderHelp()).toBe(help)
+      expect(ctx.parent?.usage()).toBe('Usage: cli.js remote [COMMAND]')
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    subcommands: {
+      remote: defineCommand({
+        meta: {},
+        options: {},
+        subcommands: {
+          add,
+        },
+      }),
+    },
+  })
+  expect(run(main, ['node', 'cli.js', 'remote', 'add', 'origin'])).toBe(0)
+})
+
+test('help sections and examples', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+      about: 'Deploy the app',
+      longAbout: 'Deploy the app to the cloud',
+      beforeHelp: 'Welcome',
+      afterHelp: 'See the docs',
+      examples: ['test --env prod', 'test --dry-run'],
+    },
+    options: {
+      env: {
+        type: 'option',
+        help: 'Target environment',
+      },
+    },
+    callback: () => {},
+  })
+  const help = runForTest(main, ['node', 'test.js', '-h']).stdout
+  expect(help.startsWith('Welcome\n\nDeploy the app\n')).toBe(true)
+  expect(help.endsWith('\nExamples:\n  test --env prod\n  test --dry-run\n\nSee the docs\n')).toBe(true)
+  const longHelp = runForTest(main, ['node', 'test.js', '--help']).stdout
+  expect(longHelp).toContain('Deploy the app to the cloud')
+  expect(longHelp).toContain('Examples:\n  test --env prod')
+})
+
+test('help template, usage and author', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+      author: 'Noctisynth',
+      usage: 'test [options] <file>',
+      helpTemplate: '{name} by {author}\n{usage-heading} {usage}',
+      nextLineHelp: true,
+    },
+    options: {},
+    callback: () => {},
+  })
+  expect(runForTest(main, ['node', 'test.js', '--help']).stdout).toBe(
+    'test by Noctisynth\nUsage: test [options] <file>\n',
+  )
+})
+
+test('option help metadata', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      out: {
+        type: 'option',
+        help: 'Output file',
+        longHelp: 'Output file of the build',
+        valueName: 'FILE',
+        displayOrder: 100,
+      },
+      set: {
+        type: 'option',
+        valueNames: ['KEY', 'VALUE'],
+        numArgs: '2',
+        helpHeading: 'Config',
+        hideShortHelp: true,
+      },
+      mode: {
+        type: 'option',
+        choices: ['dev', 'prod'],
+        hidePossibleValues: true,
+        env: 'TEST_MODE',
+        hideEnv: true,
+      },
+    },
+    callback: (ctx: Context) => ctx.args.mode,
+  })
+  const help = runForTest(main, ['node', 'test.js', '-h']).stdout
+  expect(help).toContain('  -o, --out <FILE>   Output file\n')
+  expect(help.indexOf('--out')).toBeGreaterThan(help.indexOf('--mode'))
+  expect(help).not.toContain('--set')
+  expect(help).not.toContain('dev')
+  expect(help).not.toContain('TEST_MODE')
+  const longHelp = runForTest(main, ['node', 'test.js', '--help']).stdout
+  expect(longHelp).toContain('Output file of the build')
+  expect(longHelp).toContain('Config:\n  -s, --set <KEY> <VALUE>')
+  expect(runForTest(main, ['node', 'test.js'], { env: { TEST_MODE: 'prod' } }).result).toBe('prod')
+})
@@ -0,0 +1,152 @@
+import { type Context, defineCommand, run } from 'archons'
+
+test('hooks run around the callback', () => {
+  const calls: string[] = []
+  const dev = defineCommand({
+    meta: {},
+    options: {},
+    hooks: {
+      preRun: (ctx: Context) => {
+        expect(ctx.args.verbose).toBe(true)
+        calls.push('dev:preRun')
+      },
+      postRun: () => {
+        calls.push('dev:postRun')
+      },
+    },
+    callback: () => {
+      calls.push('callback')
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        parser: 'boolean',
+        global: true,
+      },
+    },
+    hooks: {
+      preRun: () => {
+        calls.push('main:preRun')
+      },
+      postRun: () => {
+        calls.push('main:postRun')
+      },
+    },
+    subcommands: {
+      dev,
+    },
+  })
+  expect(run(main, ['node', 'test.js', 'dev', '--verbose'])).toBe(0)
+  expect(calls).toEqual(['main:preRun', 'dev:preRun', 'callback', 'dev:postRun', 'main:postRun'])
+})
+
+test('pre-run hook aborts the run', () => {
+  const calls: string[] = []
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    hooks: {
+      preRun: () => false,
+      postRun: () => {
+        calls.push('postRun')
+      },
+    },
+    callback: () => {
+      calls.push('callback')
+    },
+  })
+  run(main, ['node', 'test.js'])
+  expect(calls).toEqual([])
+})
+
+test('error hooks receive the thrown error', () => {
+  const calls: string[] = []
+  const dev = defineCommand({
+    meta: {},
+    options: {},
+    hooks: {
+      onError: (_ctx: Context, error: unknown) => {
+        calls.push(`dev:${(error as Error).message}`)
+      },
+    },
+    callback: () => {
+      throw new Error('boom')
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    hooks: {
+      postRun: () => {
+        calls.push('postRun')
+      },
+      onError: (_ctx: Context, error: unknown) => {
+        calls.push(`main:${(error as Error).message}`)
+      },
+    },
+    subcommands: {
+      dev,
+    },
+  })
+  expect(() => run(main, ['node', 'test.js', 'dev'])).toThrow('boom')
+  expect(calls).toEqual(['dev:boom', 'main:boom'])
+})
+
+test('async hooks are awaited', async () => {
+  const calls: string[] = []
+  const sleep = () => new Promise((resolve) => setTimeout(resolve, 1))
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    hooks: {
+      preRun: async () => {
+        await sleep()
+        calls.push('preRun')
+      },
+      postRun: async () => {
+        await sleep()
+        calls.push('postRun')
+      },
+    },
+    callback: async () => {
+      await sleep()
+      calls.push('callback')
+    },
+  })
+  await run(main, ['node', 'test.js'])
+  expect(calls).toEqual(['preRun', 'callback', 'postRun'])
+})
+
+test('async errors reject the run', async () => {
+  const calls: string[] = []
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    hooks: {
+      preRun: async () => {
+        throw new Error('denied')
+      },
+      onError: async (_ctx: Context, error: unknown) => {
+        calls.push((error as Error).message)
+      },
+    },
+    callback: () => {
+      calls.push('callback')
+    },
+  })
+  await expect(run(main, ['node', 'test.js'])).rejects.toThrow('denied')
+  expect(calls).toEqual(['denied'])
+})
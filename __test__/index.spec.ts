@@ -24,9 +24,7 @@ test('define command', () => {
 })
 
 test('run command', () => {
-  expect(() => {
-    run(main, ['node', 'test.js'])
-  }).not.toThrow()
+  expect(run(main, ['node', 'test.js'])).toBe(0)
 })
 
 test('run help', () => {
@@ -0,0 +1,233 @@
+import { type CommandMeta, type Context, defineCommand, getMessages, runForTest } from 'archons'
+
+function createCommand(meta: CommandMeta) {
+  return defineCommand({
+    meta: {
+      name: 'test',
+      about: 'Greet someone',
+      ...meta,
+    },
+    options: {
+      name: {
+        type: 'positional',
+        required: true,
+        help: 'Name to greet',
+      },
+      count: {
+        parser: 'number',
+        default: '1',
+        help: 'Number of greetings',
+      },
+      lang: {
+        choices: ['en', 'zh'],
+        help: 'Language',
+      },
+    },
+    subcommands: {
+      fail: defineCommand({
+        meta: {
+          about: 'Fail the greeting',
+        },
+        options: {},
+        callback: (ctx: Context) => {
+          ctx.fail('the greeting failed', { hint: 'try again' })
+        },
+      }),
+    },
+    callback: (_: Context) => {},
+  })
+}
+
+test('translate the help output', () => {
+  const result = runForTest(createCommand({ locale: 'zh-CN' }), ['node', 'test.js', '--help'])
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toContain('用法: test.js [OPTIONS] <name> [COMMAND]')
+  expect(result.stdout).toContain('命令:\n')
+  expect(result.stdout).toContain('参数:\n  <name>  Name to greet')
+  expect(result.stdout).toContain('选项:\n')
+  expect(result.stdout).toContain('Number of greetings [默认值: 1]')
+  expect(result.stdout).toContain('Language [可选值: en, zh]')
+  expect(result.stdout).toContain('help  打印此帮助或给定子命令的帮助')
+  expect(result.stdout).toContain('-h, --help           打印帮助')
+  expect(result.stdout).not.toContain('Options:')
+})
+
+test('translate the error messages', () => {
+  const cmd = createCommand({ locale: 'zh-CN' })
+  let result = runForTest(cmd, ['node', 'test.js', 'alice', '--coun', '2'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toBe(
+    [
+      "错误: 发现未知参数 '--coun'",
+      '',
+      "  提示: 存在相似的参数: '--count'",
+      '',
+      '用法: test.js --count <count> <name>',
+      '',
+      "更多信息请尝试 '--help'。",
+      '',
+    ].join('\n'),
+  )
+
+  result = runForTest(cmd, ['node', 'test.js'])
+  expect(result.stderr).toContain('错误: 缺少以下必需参数:\n  <name>\n')
+
+  result = runForTest(cmd, ['node', 'test.js', 'alice', '--lang', 'fr'])
+  expect(result.stderr).toContain("错误: 参数 '--lang <lang>' 的值 'fr' 无效\n  [可选值: en, zh]\n")
+
+  result = runForTest(cmd, ['node', 'test.js', 'alice', 'fail'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toContain('错误: the greeting failed\n\n  提示: try again\n')
+})
+
+test('keep the text written by the user', () => {
+  const cmd = defineCommand({
+    meta: {
+      name: 'test',
+      version: '1.0.0',
+      locale: 'zh-CN',
+      afterHelp: 'Options:\ntip: run with --verbose\nerror: codes are listed below',
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        action: 'store',
+        help: 'Print version',
+      },
+      port: {
+        type: 'option',
+        default: '80',
+        alias: ['p0rt'],
+        help: 'Port [default: 80]',
+      },
+    },
+    callback: (_: Context) => {},
+  })
+  const result = runForTest(cmd, ['node', 'test.js', '--help'])
+  expect(result.stdout).toContain('--verbose      Print version\n')
+  expect(result.stdout).toContain('Port [default: 80] [默认值: 80] [别名: --p0rt]\n')
+  expect(result.stdout).toContain('-V, --version      打印版本\n')
+  expect(result.stdout).toContain('\n\nOptions:\ntip: run with --verbose\nerror: codes are listed below\n')
+})
+
+test('render the same messages as clap in English', () => {
+  for (const args of [
+    ['alice', '--coun', '2'],
+    [],
+    ['alice', '--lang', 'fr'],
+    ['alice', '--count', 'x'],
+    ['alice', 'bob'],
+    ['alice', '-x'],
+    ['alice', '--lang'],
+    ['alice', 'fail'],
+    ['--help'],
+  ]) {
+    const expected = runForTest(createCommand({}), ['node', 'test.js', ...args])
+    const result = runForTest(createCommand({ locale: 'en' }), ['node', 'test.js', ...args])
+    expect(result.stdout).toBe(expected.stdout)
+    expect(result.stderr).toBe(expected.stderr)
+  }
+})
+
+test('override the messages', () => {
+  const cmd = createCommand({
+    messages: {
+      'usage-heading': 'USAGE',
+      'unexpected-argument': 'no such option {arg}',
+    },
+  })
+  let result = runForTest(cmd, ['node', 'test.js', '--help'])
+  expect(result.stdout).toContain('USAGE: test.js [OPTIONS] <name> [COMMAND]')
+  expect(result.stdout).toContain('Options:')
+
+  result = runForTest(cmd, ['node', 'test.js', 'alice', '--unknown'])
+  expect(result.stderr).toContain('error: no such option --unknown\n')
+  expect(result.stderr).toContain('USAGE: test.js')
+})
+
+test('get the messages of the locales', () => {
+  const en = getMessages()
+  const zh = getMessages('zh-CN')
+  expect(en['usage-heading']).toBe('Usage')
+  expect(zh['usage-heading']).toBe('用法')
+  expect(Object.keys(zh).sort()).toEqual(Object.keys(en).sort())
+  // @ts-expect-error the locale is checked at runtime as well
+  expect(() => getMessages('fr')).toThrow('Unsupported locale `fr`, expected `en` or `zh-CN`')
+})
+
+test('reject unsupported locales', () => {
+  // @ts-expect-error the locale is checked at runtime as well
+  const cmd = createCommand({ locale: 'fr' })
+  expect(() => runForTest(cmd, ['node', 'test.js', 'alice'])).toThrow('Unsupported locale `fr`')
+})
+
+test('render the same details and long help as clap in English', () => {
+  const createDetailed = (meta: CommandMeta) =>
+    defineCommand({
+      meta: {
+        name: 'test',
+        version: '1.0.0',
+        ...meta,
+      },
+      options: {
+        host: {
+          type: 'positional',
+          default: 'localhost',
+          help: 'Host to connect',
+        },
+        port: {
+          env: 'ARCHONS_TEST_PORT',
+          default: '80',
+          alias: ['p0rt'],
+          help: 'Port',
+          longHelp: 'Port to connect\nDefaults to the HTTP port',
+        },
+        mode: {
+          choices: ['fast', 'safe mode'],
+        },
+      },
+      subcommands: {
+        sub: defineCommand({
+          meta: {
+            about: 'Subcommand',
+          },
+          options: {},
+          callback: (_: Context) => {},
+        }),
+      },
+      callback: (_: Context) => {},
+    })
+  for (const args of [['--help'], ['-h'], ['sub', '--help'], ['help', 'sub'], ['help', 'nope'], ['--version']]) {
+    const expected = runForTest(createDetailed({}), ['node', 'test.js', ...args], { env: { ARCHONS_TEST_PORT: '8080' } })
+    const result = runForTest(createDetailed({ locale: 'en' }), ['node', 'test.js', ...args], {
+      env: { ARCHONS_TEST_PORT: '8080' },
+    })
+    expect(result.stdout).toBe(expected.stdout)
+    expect(result.stderr).toBe(expected.stderr)
+  }
+})
+
+test('translate the help of the multicall applets', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'mycli',
+      multicall: true,
+      locale: 'zh-CN',
+    },
+    options: {},
+    subcommands: {
+      'mycli-fmt': defineCommand({
+        meta: {
+          about: 'Format files',
+        },
+        options: {},
+        callback: (_: Context) => {},
+      }),
+    },
+  })
+  const result = runForTest(main, ['node', '/usr/bin/mycli-fmt', '--help'])
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toContain('用法: mycli-fmt')
+  expect(result.stdout).toContain('-h, --help  打印帮助')
+  expect(runForTest(main, ['node', '/usr/bin/mycli-fmt']).exitCode).toBe(0)
+})
@@ -0,0 +1,126 @@
+import { type Context, defineCommand, run, runForTest } from 'archons'
+
+test('map option', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      define: {
+        type: 'option',
+        short: 'D',
+        action: 'map',
+      },
+      header: {
+        type: 'option',
+        short: 'H',
+        action: 'map',
+        mapSeparator: ':',
+        mapDuplicateKey: 'array',
+      },
+      port: {
+        type: 'option',
+        short: 'p',
+        action: 'map',
+        parser: 'number',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({
+        define: { a: '2', b: 'x=y' },
+        header: { 'X-A': ['b', 'c'] },
+        port: { http: 80 },
+      })
+    },
+  })
+  expect(
+    run(main, [
+      'node',
+      'test.js',
+      '-D',
+      'a=1',
+      '-D',
+      'b=x=y',
+      '-D',
+      'a=2',
+      '--header',
+      'X-A: b',
+      '--header',
+      'X-A: c',
+      '--port',
+      'http=80',
+    ]),
+  ).toBe(0)
+})
+
+test('map option duplicate key', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      label: {
+        type: 'option',
+        action: 'map',
+        mapDuplicateKey: 'error',
+      },
+    },
+    callback: (_: Context) => {},
+  })
+  const result = runForTest(main, ['node', 'test.js', '--label', 'env=prod', '--label', 'env=dev'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toBe(
+    "error: duplicate key 'env' for '--label <KEY=VALUE>'\n\nUsage: test.js [OPTIONS]\n\nFor more information, try '--help'.\n",
+  )
+  expect(run(main, ['node', 'test.js', '--label', 'env=prod', '--label', 'os=linux'])).toBe(0)
+})
+
+test('map option keeps prototype keys', () => {
+  let label: Record<string, unknown> = {}
+  let header: Record<string, unknown> = {}
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      label: {
+        type: 'option',
+        action: 'map',
+      },
+      header: {
+        type: 'option',
+        action: 'map',
+        mapDuplicateKey: 'array',
+      },
+    },
+    callback: (ctx: Context) => {
+      label = ctx.args.label
+      header = ctx.args.header
+    },
+  })
+  run(main, ['node', 'test.js', '--label', '__proto__=x', '--header', '__proto__=a', '--header', 'constructor=b'])
+  expect(Object.getPrototypeOf(label)).toBe(null)
+  expect(Object.keys(label)).toEqual(['__proto__'])
+  expect(label.__proto__).toBe('x')
+  expect(Object.keys(header)).toEqual(['__proto__', 'constructor'])
+  expect(header.__proto__).toEqual(['a'])
+  expect(header.constructor).toEqual(['b'])
+})
+
+test('map option duplicate key policy is validated', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      label: {
+        type: 'option',
+        action: 'map',
+        // @ts-expect-error unsupported policy
+        mapDuplicateKey: 'first',
+      },
+    },
+    callback: (_: Context) => {},
+  })
+  expect(() => run(main, ['node', 'test.js'])).toThrow('Unsupported duplicate key policy `first` of option `label`')
+})
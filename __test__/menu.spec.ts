@@ -0,0 +1,107 @@
+import { type Context, defineCommand, runForTest } from 'archons'
+
+const calls: string[] = []
+const exec = defineCommand({
+  meta: {
+    name: 'run',
+    about: 'Run a script',
+  },
+  options: {
+    script: {
+      type: 'positional',
+    },
+  },
+  callback: (ctx: Context) => {
+    calls.push(`run:${ctx.args.script}`)
+  },
+})
+
+const main = defineCommand({
+  meta: {
+    name: 'test',
+    interactiveMenu: true,
+  },
+  options: {},
+  subcommands: {
+    build: defineCommand({
+      meta: {
+        about: 'Build the project',
+      },
+      options: {
+        target: {
+          type: 'option',
+          required: true,
+        },
+      },
+      callback: (ctx: Context) => {
+        calls.push(`build:${ctx.args.target}`)
+      },
+    }),
+    db: defineCommand({
+      meta: {
+        about: 'Manage the database',
+      },
+      options: {},
+      subcommands: {
+        migrate: defineCommand({
+          meta: {},
+          options: {},
+          callback: () => {
+            calls.push('db:migrate')
+          },
+        }),
+      },
+    }),
+    exec,
+  },
+})
+
+test('picks the command from the menu', () => {
+  calls.length = 0
+  const result = runForTest(main, ['node', 'test.js'], {
+    answers: ['build', 'web'],
+  })
+  expect(result.exitCode).toBe(0)
+  expect(calls).toEqual(['build:web'])
+  expect(result.stdout).toBe('> Select a command build\n> target web\n')
+})
+
+test('recurses into the nested subcommands', () => {
+  calls.length = 0
+  expect(runForTest(main, ['node', 'test.js'], { answers: ['db', 'migrate'] }).exitCode).toBe(0)
+  expect(runForTest(main, ['node', 'test.js', 'db'], { answers: ['migrate'] }).exitCode).toBe(0)
+  expect(calls).toEqual(['db:migrate', 'db:migrate'])
+})
+
+test('menu is skipped when a command is given', () => {
+  calls.length = 0
+  const result = runForTest(main, ['node', 'test.js', 'build', '--target', 'cli'])
+  expect(result.stdout).toBe('')
+  expect(calls).toEqual(['build:cli'])
+})
+
+test('picks the renamed command before the escaped arguments', () => {
+  calls.length = 0
+  expect(runForTest(main, ['node', 'test.js'], { answers: ['run'] }).exitCode).toBe(0)
+  expect(() => runForTest(main, ['node', 'test.js'], { answers: ['exec'] })).toThrow(
+    'Invalid choice `exec` for the prompt `Select a command`',
+  )
+
+  const escaped = defineCommand({
+    meta: {
+      name: 'test',
+      interactiveMenu: true,
+    },
+    options: {
+      files: {
+        type: 'positional',
+        last: true,
+      },
+    },
+    subcommands: {
+      exec,
+    },
+  })
+  expect(runForTest(escaped, ['node', 'test.js', '--', 'start'], { answers: ['run'] }).exitCode).toBe(0)
+  expect(calls).toEqual(['run:undefined', 'run:start'])
+})
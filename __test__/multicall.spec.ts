@@ -0,0 +1,96 @@
+import { type Context, defineCommand, runForTest } from 'archons'
+
+const calls: string[] = []
+const fmt = defineCommand({
+  meta: {
+    about: 'Format files',
+  },
+  options: {
+    check: {
+      type: 'option',
+      parser: 'boolean',
+    },
+  },
+  callback: (ctx: Context) => {
+    calls.push(`fmt:${ctx.args.check}`)
+  },
+})
+const admin = defineCommand({
+  meta: {},
+  options: {},
+  callback: () => {
+    calls.push('admin')
+  },
+})
+const main = defineCommand({
+  meta: {
+    name: 'mycli',
+    multicall: true,
+  },
+  options: {},
+  subcommands: {
+    'mycli-fmt': fmt,
+    'mycli-admin': admin,
+    mycli: defineCommand({
+      meta: {},
+      options: {},
+      subcommands: {
+        fmt,
+        admin,
+      },
+    }),
+  },
+})
+
+test('applet is selected by the executable name', () => {
+  calls.length = 0
+  expect(runForTest(main, ['node', '/usr/bin/mycli-fmt', '--check']).exitCode).toBe(0)
+  expect(runForTest(main, ['node', '/usr/lib/mycli-admin.js']).exitCode).toBe(0)
+  expect(runForTest(main, ['node', 'mycli', 'fmt']).exitCode).toBe(0)
+  expect(calls).toEqual(['fmt:true', 'admin', 'fmt:false'])
+})
+
+test('applet has its own help', () => {
+  const result = runForTest(main, ['node', '/usr/bin/mycli-fmt', '--help'])
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toContain('Format files')
+  expect(result.stdout).toContain('Usage: mycli-fmt [OPTIONS]')
+})
+
+test('applet context is displayed by its own name', () => {
+  const applet = defineCommand({
+    meta: {},
+    options: {},
+    callback: (ctx: Context) => ctx.usage(),
+  })
+  const cli = defineCommand({
+    meta: {
+      name: 'mycli',
+      multicall: true,
+    },
+    options: {},
+    subcommands: {
+      'mycli-fmt': applet,
+    },
+  })
+  expect(runForTest(cli, ['node', '/usr/bin/mycli-fmt']).result).toBe('Usage: mycli-fmt')
+})
+
+test('multicall command can not have options', () => {
+  const invalid = defineCommand({
+    meta: {
+      name: 'mycli',
+      multicall: true,
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        parser: 'boolean',
+      },
+    },
+    subcommands: {
+      'mycli-fmt': fmt,
+    },
+  })
+  expect(() => runForTest(invalid, ['node', 'mycli-fmt'])).toThrow('The multicall command can not have options')
+})
@@ -1,6 +1,6 @@
 import { spawnSync } from 'node:child_process'
 
-import { type Context, defineCommand, run } from 'archons'
+import { type Context, defineCommand, run, runForTest } from 'archons'
 
 test('positional option', () => {
   const main = defineCommand({
@@ -16,9 +16,7 @@ test('positional option', () => {
       expect(ctx.args.foo).toBe('foo')
     },
   })
-  expect(() => {
-    run(main, ['node', 'test.js', 'foo'])
-  }).not.toThrow()
+  expect(run(main, ['node', 'test.js', 'foo'])).toBe(0)
 })
 
 test('required positional option', () => {
@@ -50,10 +48,221 @@ test('boolean flag', () => {
       expect(ctx.args.verbose).toBe(ctx.args.eq)
     },
   })
-  expect(() => {
-    run(main, ['node', 'test.js', '--verbose', '-e'])
-  }).not.toThrow()
+  expect(run(main, ['node', 'test.js', '--verbose', '-e'])).toBe(0)
+  expect(run(main, ['node', 'test.js'])).toBe(0)
+})
+
+test('negatable flag', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      color: {
+        type: 'option',
+        parser: 'boolean',
+        negatable: true,
+        default: 'true',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({ color: ctx.rawArgs.at(-1) !== '--no-color' })
+    },
+  })
+  expect(run(main, ['node', 'test.js'])).toBe(0)
+  expect(run(main, ['node', 'test.js', '--no-color'])).toBe(0)
+  expect(run(main, ['node', 'test.js', '--no-color', '--color'])).toBe(0)
+  expect(run(main, ['node', 'test.js', '--color', '--no-color'])).toBe(0)
+
+  expect(runForTest(main, ['node', 'test.js', '--help']).stdout).toContain('  -c, --[no-]color')
+  const result = runForTest(main, ['node', 'test.js', '--[no-]color'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toContain("unexpected argument '--[no-]color' found")
+
+  const invalid = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      color: {
+        type: 'option',
+        negatable: true,
+      },
+    },
+    callback: () => {},
+  })
+  expect(() => run(invalid, ['node', 'test.js'])).toThrow('Negatable option `color` must be a boolean flag')
+})
+
+test('negate the flag set by the environment variable', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      color: {
+        type: 'option',
+        parser: 'boolean',
+        negatable: true,
+        env: 'XCOLOR',
+      },
+    },
+    callback: (ctx: Context) => ctx.args.color,
+  })
+  const options = { env: { XCOLOR: 'true' } }
+  expect(runForTest(main, ['node', 'test.js'], options).result).toBe(true)
+  let result = runForTest(main, ['node', 'test.js', '--no-color'], options)
+  expect(result.exitCode).toBe(0)
+  expect(result.result).toBe(false)
+  result = runForTest(main, ['node', 'test.js', '--no-color', '--color'], options)
+  expect(result.result).toBe(true)
+  result = runForTest(main, ['node', 'test.js', '--color', '--no-color'], options)
+  expect(result.result).toBe(false)
+})
+
+test('value delimiter', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      targets: {
+        type: 'option',
+        valueDelimiter: ',',
+      },
+      ports: {
+        type: 'option',
+        parser: 'number',
+        action: 'append',
+        valueDelimiter: ',',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({ targets: ['a', 'b', 'c'], ports: [80, 443, 8080] })
+    },
+  })
+  expect(run(main, ['node', 'test.js', '--targets', 'a,b,c', '--ports', '80,443', '--ports', '8080'])).toBe(0)
+})
+
+test('kebab-case option names', () => {
+  const sub = defineCommand({
+    meta: {},
+    options: {
+      outDir: {
+        type: 'option',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({ dryRun: true, outDir: 'dist' })
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+      renameAll: 'kebab-case',
+    },
+    options: {
+      dryRun: {
+        type: 'option',
+        parser: 'boolean',
+        global: true,
+      },
+    },
+    subcommands: {
+      sub,
+    },
+  })
+  expect(run(main, ['node', 'test.js', 'sub', '--dry-run', '--out-dir', 'dist'])).toBe(0)
+})
+
+test('namespaced options', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      'db.host': {
+        type: 'option',
+      },
+      'db.port': {
+        type: 'option',
+        parser: 'number',
+        default: '5432',
+      },
+      'cache.ttl': {
+        type: 'option',
+        parser: 'number',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({ db: { host: 'localhost', port: 5432 }, cache: { ttl: 60 } })
+      expect(ctx.get('db.host')).toBe('localhost')
+    },
+  })
+  expect(run(main, ['node', 'test.js', '--db.host', 'localhost', '--cache.ttl', '60'])).toBe(0)
+
+  const conflicting = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      db: {
+        type: 'option',
+      },
+      'db.host': {
+        type: 'option',
+      },
+    },
+    callback: () => {},
+  })
+  expect(() => run(conflicting, ['node', 'test.js'])).toThrow(
+    'Option `db` conflicts with the namespace of option `db.host`',
+  )
+})
+
+test('short option collisions', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+      version: '1.0.0',
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        parser: 'boolean',
+      },
+      vendor: {
+        type: 'option',
+      },
+      header: {
+        type: 'option',
+      },
+      value: {
+        type: 'option',
+        short: false,
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({ verbose: true, header: 'x', vendor: 'npm', value: '1' })
+    },
+  })
+  expect(run(main, ['node', 'test.js', '--verbose', '--header', 'x', '-v', 'npm', '--value', '1'])).toBe(0)
+})
+
+test('explicit short option conflict', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      header: {
+        type: 'option',
+        short: 'h',
+      },
+    },
+    callback: (_: Context) => {},
+  })
   expect(() => {
     run(main, ['node', 'test.js'])
-  }).not.toThrow()
+  }).toThrow('Short option `-h` of `--header` conflicts with `--help`')
 })
@@ -0,0 +1,51 @@
+import { type Context, defineCommand, run } from 'archons'
+
+test('last positional', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      cmd: {
+        type: 'positional',
+        last: true,
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args.cmd).toEqual(['npm', 'test', '--watch'])
+    },
+  })
+  expect(run(main, ['node', 'test.js', '--', 'npm', 'test', '--watch'])).toBe(0)
+})
+
+test('trailing var arg', () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      script: {
+        type: 'positional',
+        required: true,
+      },
+      rest: {
+        type: 'positional',
+        trailingVarArg: true,
+        allowHyphenValues: true,
+      },
+      offset: {
+        type: 'option',
+        parser: 'number',
+        allowNegativeNumbers: true,
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({
+        script: 'script.js',
+        rest: ['--flag-for-script', 'value'],
+        offset: -1,
+      })
+    },
+  })
+  expect(run(main, ['node', 'test.js', '--offset', '-1', 'script.js', '--flag-for-script', 'value'])).toBe(0)
+})
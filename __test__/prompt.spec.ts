@@ -0,0 +1,118 @@
+import { type Context, defineCommand, runForTest } from 'archons'
+
+let args: Record<string, unknown> = {}
+const main = defineCommand({
+  meta: {
+    name: 'deploy',
+  },
+  options: {
+    env: {
+      type: 'option',
+      required: true,
+      choices: ['dev', 'prod'],
+    },
+    replicas: {
+      type: 'option',
+      parser: 'number',
+      prompt: 'How many replicas?',
+    },
+    token: {
+      type: 'option',
+      required: true,
+      sensitive: true,
+    },
+    yes: {
+      type: 'option',
+      parser: 'boolean',
+      prompt: true,
+    },
+    verbose: {
+      type: 'option',
+      parser: 'boolean',
+    },
+  },
+  callback: (ctx: Context) => {
+    args = ctx.args
+  },
+})
+
+test('missing options fail without the interactive mode', () => {
+  const result = runForTest(main, ['node', 'deploy'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toContain('the following required arguments were not provided')
+})
+
+test('missing options are prompted in the interactive mode', () => {
+  args = {}
+  const result = runForTest(main, ['node', 'deploy', '--token', 'secret'], {
+    interactive: true,
+    answers: ['prod', 'three', '3', true],
+  })
+  expect(result.exitCode).toBe(0)
+  expect(args).toEqual({ env: 'prod', replicas: 3, token: 'secret', yes: true, verbose: false })
+  expect(result.stdout).toBe('> env prod\n> How many replicas? three\n> How many replicas? 3\n> yes Yes\n')
+  expect(result.stderr).toBe('error: invalid number `three`\n')
+})
+
+test('sensitive options are masked', () => {
+  const result = runForTest(main, ['node', 'deploy', '--env', 'dev', '--replicas', '1', '--yes'], {
+    interactive: true,
+    answers: ['secret'],
+  })
+  expect(result.exitCode).toBe(0)
+  expect(args).toEqual({ env: 'dev', replicas: 1, token: 'secret', yes: true, verbose: false })
+  expect(result.stdout).toBe('> token ********\n')
+})
+
+test('choices are validated', () => {
+  const result = runForTest(main, ['node', 'deploy', '--env', 'test'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toContain("invalid value 'test' for '--env <env>'")
+})
+
+test('conflicting options are not prompted', () => {
+  const login = defineCommand({
+    meta: {
+      name: 'login',
+    },
+    options: {
+      token: {
+        type: 'option',
+        required: true,
+        conflictsWith: ['user'],
+      },
+      user: {
+        type: 'option',
+        required: true,
+      },
+    },
+    callback: (ctx: Context) => {
+      args = ctx.args
+    },
+  })
+  args = {}
+  let result = runForTest(login, ['node', 'login', '--user', 'admin'], { interactive: true })
+  expect(result.exitCode).toBe(0)
+  expect(args).toEqual({ user: 'admin' })
+
+  result = runForTest(login, ['node', 'login'], { interactive: true, answers: ['secret'] })
+  expect(result.exitCode).toBe(0)
+  expect(args).toEqual({ token: 'secret' })
+})
+
+test('choices are not supported by map options', () => {
+  const invalid = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      header: {
+        type: 'option',
+        action: 'map',
+        choices: ['a=b'],
+      },
+    },
+    callback: () => {},
+  })
+  expect(() => runForTest(invalid, ['node', 'test'])).toThrow('Choices of option `header` are not supported by `map`')
+})
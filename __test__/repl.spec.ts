@@ -0,0 +1,111 @@
+import { type Context, defineCommand, repl, runForTest } from 'archons'
+
+const calls: string[] = []
+const main = defineCommand({
+  meta: {
+    name: 'test',
+  },
+  options: {},
+  subcommands: {
+    add: defineCommand({
+      meta: {},
+      options: {
+        name: {
+          type: 'positional',
+        },
+      },
+      callback: (ctx: Context) => {
+        calls.push(ctx.args.name)
+      },
+    }),
+    fail: defineCommand({
+      meta: {},
+      options: {},
+      callback: () => {
+        throw new Error('boom')
+      },
+    }),
+    wait: defineCommand({
+      meta: {},
+      options: {},
+      callback: async () => {
+        await new Promise((resolve) => setTimeout(resolve, 1))
+        calls.push('waited')
+      },
+    }),
+    login: defineCommand({
+      meta: {},
+      options: {
+        user: {
+          type: 'positional',
+        },
+      },
+      callback: (ctx: Context) => {
+        ctx.set('user', ctx.args.user)
+      },
+    }),
+    whoami: defineCommand({
+      meta: {},
+      options: {},
+      callback: (ctx: Context) => {
+        calls.push(ctx.getState('user') ?? 'anonymous')
+      },
+    }),
+  },
+})
+
+const shell = defineCommand({
+  meta: {
+    name: 'test',
+  },
+  options: {},
+  callback: () => repl(main, { prompt: 'test>' }),
+})
+
+test('runs the lines until exit', () => {
+  calls.length = 0
+  const result = runForTest(shell, ['node', 'test.js'], {
+    stdin: ['add "hello world"', "add it\\'s", '', 'exit', 'add ignored'].join('\n'),
+  })
+  expect(calls).toEqual(['hello world', "it's"])
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toContain('> test> add "hello world"\n')
+})
+
+test('keeps running after errors', () => {
+  calls.length = 0
+  const result = runForTest(shell, ['node', 'test.js'], {
+    stdin: ['unknown', 'fail', 'add "oops', 'add ok'].join('\n'),
+  })
+  expect(calls).toEqual(['ok'])
+  expect(result.exitCode).toBe(0)
+  expect(result.stderr).toContain("unrecognized subcommand 'unknown'")
+  expect(result.stderr).toContain('error: boom\n')
+  expect(result.stderr).toContain('error: unterminated double quote\n')
+})
+
+test('prints the help of the commands', () => {
+  const result = runForTest(shell, ['node', 'test.js'], {
+    stdin: ['help', 'help add'].join('\n'),
+  })
+  expect(result.stdout).toContain('Usage: test [COMMAND]')
+  expect(result.stdout).toContain('Usage: test add [name]')
+})
+
+test('waits for async lines', async () => {
+  calls.length = 0
+  const result = await runForTest(shell, ['node', 'test.js'], {
+    stdin: ['wait', 'add done'].join('\n'),
+  })
+  expect(calls).toEqual(['waited', 'done'])
+  expect(result.exitCode).toBe(0)
+})
+
+test('shares the state between the lines', async () => {
+  calls.length = 0
+  const result = await runForTest(shell, ['node', 'test.js'], {
+    stdin: ['whoami', 'login admin', 'wait', 'whoami'].join('\n'),
+  })
+  expect(calls).toEqual(['anonymous', 'waited', 'admin'])
+  expect(result.exitCode).toBe(0)
+})
@@ -0,0 +1,57 @@
+import { type Context, defineCommand, run } from 'archons'
+
+test('share state from parent hooks', () => {
+  const dev = defineCommand({
+    meta: {},
+    options: {
+      port: {
+        type: 'option',
+        parser: 'number',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.state).toEqual({ token: 'secret', db: { connected: true } })
+      expect(ctx.getState('token')).toBe('secret')
+      expect(ctx.get('port')).toBe(3000)
+      ctx.set('port', 8080)
+      expect(ctx.getState('port')).toBe(8080)
+      expect(ctx.state.port).toBe(8080)
+      expect(ctx.get('port')).toBe(3000)
+      expect(ctx.args.port).toBe(3000)
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    provide: () => ({ db: { connected: true } }),
+    hooks: {
+      preRun: (ctx: Context) => {
+        expect(ctx.state.db).toEqual({ connected: true })
+        ctx.set('token', 'secret')
+      },
+    },
+    subcommands: {
+      dev,
+    },
+  })
+  expect(run(main, ['node', 'test.js', 'dev', '--port', '3000'])).toBe(0)
+})
+
+test('async state providers are awaited', async () => {
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    provide: async () => {
+      await new Promise((resolve) => setTimeout(resolve, 1))
+      return { user: 'admin' }
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.getState('user')).toBe('admin')
+    },
+  })
+  expect(await run(main, ['node', 'test.js'])).toBe(0)
+})
@@ -0,0 +1,57 @@
+import { basename } from 'node:path'
+import { type Context, defineCommand, parseString, runString } from 'archons'
+
+test('parse string with shell quoting rules', () => {
+  expect(parseString(`deploy --env 'prod eu' --yes`)).toEqual(['deploy', '--env', 'prod eu', '--yes'])
+  expect(parseString(`say "a \\"quoted\\" $HOME" it\\'s ''`)).toEqual(['say', 'a "quoted" $HOME', "it's", ''])
+  expect(parseString('  ')).toEqual([])
+  expect(() => parseString(`say 'oops`)).toThrow('unterminated single quote')
+})
+
+test('run command from string', () => {
+  let args: Record<string, unknown> = {}
+  const deploy = defineCommand({
+    meta: {},
+    options: {
+      env: {
+        type: 'option',
+      },
+      yes: {
+        type: 'option',
+        parser: 'boolean',
+      },
+    },
+    callback: (ctx: Context) => {
+      args = ctx.args
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    subcommands: {
+      deploy,
+    },
+  })
+  expect(runString(main, `deploy --env 'prod eu' --yes`)).toBe(0)
+  expect(args).toEqual({ env: 'prod eu', yes: true })
+})
+
+test('name the command by the script without meta.name', () => {
+  const script = basename(process.argv[1])
+  let info: Record<string, unknown> = {}
+  const main = defineCommand({
+    meta: {},
+    options: {
+      env: {
+        type: 'option',
+      },
+    },
+    callback: (ctx: Context) => {
+      info = { binName: ctx.binName, commandPath: ctx.commandPath, usage: ctx.usage() }
+    },
+  })
+  expect(runString(main, '--env prod')).toBe(0)
+  expect(info).toEqual({ binName: script, commandPath: [script], usage: `Usage: ${script} [OPTIONS]` })
+})
@@ -21,7 +21,56 @@ test('sub command', () => {
       cmd,
     },
   })
-  expect(() => {
-    run(main, ['node.exe', 'test.js', 'cmd', 'foo'])
-  }).not.toThrow()
+  expect(run(main, ['node.exe', 'test.js', 'cmd', 'foo'])).toBe(0)
+})
+
+test('matched command path and parent contexts', () => {
+  const add = defineCommand({
+    meta: {
+      about: 'Add a remote',
+    },
+    options: {
+      url: {
+        type: 'positional',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.binName).toBe('cli.js')
+      expect(ctx.commandPath).toEqual(['test', 'remote', 'add'])
+      expect(ctx.command).toEqual({ name: 'add', about: 'Add a remote' })
+      expect(ctx.args).toEqual({ verbose: true, force: true, url: 'origin' })
+      expect(ctx.parent?.commandPath).toEqual(['test', 'remote'])
+      expect(ctx.parent?.args).toEqual({ verbose: true, force: true })
+      expect(ctx.parent?.parent?.args).toEqual({ verbose: true })
+      expect(ctx.parent?.parent?.parent).toBe(null)
+    },
+  })
+  const remote = defineCommand({
+    meta: {},
+    options: {
+      force: {
+        type: 'option',
+        parser: 'boolean',
+      },
+    },
+    subcommands: {
+      add,
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        parser: 'boolean',
+        global: true,
+      },
+    },
+    subcommands: {
+      remote,
+    },
+  })
+  expect(run(main, ['node.exe', '/usr/bin/cli.js', '--verbose', 'remote', '--force', 'add', 'origin'])).toBe(0)
 })
@@ -0,0 +1,95 @@
+import { type Context, defineCommand, runForTest } from 'archons'
+
+const main = defineCommand({
+  meta: {
+    name: 'test',
+  },
+  options: {
+    name: {
+      type: 'option',
+      parser: 'string',
+    },
+  },
+  callback: (ctx: Context) => {
+    console.log(`hello ${ctx.args.name}`)
+    console.error('done')
+    return 3
+  },
+})
+
+test('captures output and exit code of the callback', () => {
+  const result = runForTest(main, ['node', 'test.js', '--name', 'archons'])
+  expect(result).toEqual({
+    stdout: 'hello archons\n',
+    stderr: 'done\n',
+    exitCode: 3,
+    result: 3,
+  })
+  expect(process.exitCode).toBeUndefined()
+})
+
+test('captures help and usage errors', () => {
+  const help = runForTest(main, ['node', 'test.js', '--help'])
+  expect(help.exitCode).toBe(0)
+  expect(help.stdout).toContain('Usage: test')
+  const error = runForTest(main, ['node', 'test.js', '--unknown'])
+  expect(error.exitCode).toBe(2)
+  expect(error.stdout).toBe('')
+  expect(error.stderr).toContain("unexpected argument '--unknown'")
+})
+
+test('answers prompts and sets environment variables', () => {
+  const cmd = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    callback: (ctx: Context) => {
+      const name = ctx.ask('Name?')
+      const sure = ctx.confirm('Sure?')
+      return `${process.env.GREETING} ${name} ${sure}`
+    },
+  })
+  const result = runForTest(cmd, ['node', 'test.js'], {
+    env: { GREETING: 'hi' },
+    answers: ['archons'],
+    stdin: 'y\n',
+  })
+  expect(result.result).toBe('hi archons true')
+  expect(result.stdout).toBe('> Name? archons\n> Sure? Yes\n')
+  expect(process.env.GREETING).toBeUndefined()
+})
+
+test('awaits async callbacks', async () => {
+  const cmd = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+    callback: async (ctx: Context) => {
+      await new Promise((resolve) => setTimeout(resolve, 1))
+      ctx.fail('broken')
+    },
+  })
+  const result = await runForTest(cmd, ['node', 'test.js'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toContain('error: broken')
+})
+
+test('restores the process after a run throws', () => {
+  const cmd = defineCommand({
+    meta: {
+      name: 'test',
+    },
+    options: {},
+  })
+  expect(() => runForTest(cmd, ['node', 'test.js'])).toThrow('No callback function found')
+  const result = runForTest(main, ['node', 'test.js', '--name', 'again'])
+  expect(result.exitCode).toBe(3)
+  expect(result.stdout).toBe('hello again\n')
+})
+
+test('rejects empty arguments', () => {
+  expect(() => runForTest(main, [])).toThrow('The arguments must start with the executable')
+  expect(runForTest(main, ['node', 'test.js']).exitCode).toBe(3)
+})
@@ -0,0 +1,157 @@
+import { type CommandMeta, type Context, defineCommand, run, runForTest } from 'archons'
+
+const ESC = '\u001b['
+
+function createCommand(meta: CommandMeta, callback = (_: Context) => {}) {
+  return defineCommand({
+    meta: {
+      name: 'test',
+      ...meta,
+    },
+    options: {
+      name: {
+        type: 'positional',
+        help: 'Name to greet',
+      },
+      count: {
+        parser: 'number',
+        help: 'Number of greetings',
+      },
+    },
+    subcommands: {
+      sub: defineCommand({
+        meta: {
+          about: 'Subcommand',
+        },
+        options: {},
+        callback,
+      }),
+    },
+    callback,
+  })
+}
+
+const theme = {
+  header: { color: 'magenta', effects: ['bold'] },
+  literal: { color: '208' },
+  placeholder: { color: '#ff8800', background: 'bright-black' },
+  error: { color: 'bright-red', effects: ['bold', 'underline'] },
+}
+
+test('render help with the theme when colors are always enabled', () => {
+  const result = runForTest(createCommand({ theme, color: 'always' }), ['node', 'test.js', '--help'])
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toContain(`${ESC}1m${ESC}35mOptions:`)
+  expect(result.stdout).toContain(`${ESC}38;5;208m--count`)
+  expect(result.stdout).toContain(`${ESC}100m<count>`)
+  expect(result.stdout).toContain(`${ESC}38;2;255;136;0`)
+})
+
+test('render errors with the theme', () => {
+  const result = runForTest(createCommand({ theme, color: 'always' }), ['node', 'test.js', '--count', 'x'])
+  expect(result.exitCode).toBe(2)
+  expect(result.stderr).toContain(`${ESC}1m${ESC}4m${ESC}91merror:`)
+})
+
+test('render help without colors', () => {
+  let result = runForTest(createCommand({ theme, color: 'never' }), ['node', 'test.js', '--help'])
+  expect(result.stdout).toContain('Options:')
+  expect(result.stdout).not.toContain(ESC)
+
+  // The captured output is not a TTY
+  result = runForTest(createCommand({ theme }), ['node', 'test.js', '--help'])
+  expect(result.stdout).not.toContain(ESC)
+})
+
+test('render help with the presets', () => {
+  let result = runForTest(createCommand({ theme: 'styled', color: 'always' }), ['node', 'test.js', '--help'])
+  expect(result.stdout).toContain(`${ESC}1m${ESC}32mOptions:`)
+
+  result = runForTest(createCommand({ theme: { preset: 'styled', header: { color: 'blue' } }, color: 'always' }), [
+    'node',
+    'test.js',
+    '--help',
+  ])
+  expect(result.stdout).toContain(`${ESC}34mOptions:`)
+  expect(result.stdout).toContain(`${ESC}1m${ESC}36m--count`)
+
+  result = runForTest(createCommand({ theme: 'plain', color: 'always' }), ['node', 'test.js', '--help'])
+  expect(result.stdout).toContain('Options:')
+  expect(result.stdout).not.toContain(ESC)
+})
+
+test('inherit the theme and colors by subcommands', () => {
+  const result = runForTest(createCommand({ theme, color: 'always' }), ['node', 'test.js', 'sub', '--help'])
+  expect(result.stdout).toContain('Subcommand')
+  expect(result.stdout).toContain(`${ESC}1m${ESC}35mOptions:`)
+
+  let help = ''
+  const cmd = createCommand({ theme: 'styled', color: 'always' }, (ctx: Context) => {
+    help = ctx.help()
+  })
+  run(cmd, ['node', 'test.js', 'sub'])
+  expect(help).toContain(`${ESC}1m${ESC}32mOptions:`)
+})
+
+describe('color detection', () => {
+  const { FORCE_COLOR, NO_COLOR } = process.env
+
+  afterEach(() => {
+    // Assigning `undefined` to `process.env` sets the string 'undefined'
+    if (FORCE_COLOR === undefined) delete process.env.FORCE_COLOR
+    else process.env.FORCE_COLOR = FORCE_COLOR
+    if (NO_COLOR === undefined) delete process.env.NO_COLOR
+    else process.env.NO_COLOR = NO_COLOR
+  })
+
+  function renderHelp(meta: CommandMeta) {
+    let help = ''
+    run(
+      createCommand(meta, (ctx: Context) => {
+        help = ctx.help()
+      }),
+      ['node', 'test.js'],
+    )
+    return help
+  }
+
+  test('force colors with FORCE_COLOR', () => {
+    delete process.env.NO_COLOR
+    process.env.FORCE_COLOR = '1'
+    expect(renderHelp({ theme })).toContain(`${ESC}1m${ESC}35mOptions:`)
+    expect(renderHelp({ theme, color: 'never' })).not.toContain(ESC)
+    process.env.FORCE_COLOR = '0'
+    expect(renderHelp({ theme })).not.toContain(ESC)
+  })
+
+  test('disable colors with NO_COLOR', () => {
+    delete process.env.FORCE_COLOR
+    process.env.NO_COLOR = '1'
+    expect(renderHelp({ theme })).not.toContain(ESC)
+    expect(renderHelp({ theme, color: 'always' })).toContain(`${ESC}1m${ESC}35mOptions:`)
+  })
+
+  test('render help without theme as plain text', () => {
+    delete process.env.NO_COLOR
+    process.env.FORCE_COLOR = '1'
+    expect(renderHelp({})).not.toContain(ESC)
+  })
+})
+
+test('print help the same as rendering it', () => {
+  const printHelp = (meta: CommandMeta) => {
+    let help = ''
+    const result = runForTest(
+      createCommand(meta, (ctx: Context) => {
+        help = ctx.help()
+        ctx.printHelp()
+      }),
+      ['node', 'test.js'],
+    )
+    expect(result.stdout).toBe(help)
+    return result.stdout
+  }
+  expect(printHelp({ color: 'always' })).not.toContain(ESC)
+  expect(printHelp({ theme, color: 'always' })).toContain(`${ESC}1m${ESC}35mOptions:`)
+  expect(printHelp({ theme, color: 'never' })).not.toContain(ESC)
+})
@@ -0,0 +1,86 @@
+import { type Context, defineCommand, run } from 'archons'
+
+test('collect unknown args', () => {
+  const exec = defineCommand({
+    meta: {},
+    options: {
+      target: {
+        type: 'positional',
+      },
+      fast: {
+        type: 'option',
+        parser: 'boolean',
+      },
+    },
+    callback: (ctx: Context) => {
+      expect(ctx.args).toEqual({ verbose: true, target: 'app', fast: true })
+      expect(ctx.unknownArgs).toEqual(['--foo', 'bar', '--mode=dev', '-x'])
+    },
+  })
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+      allowUnknownArgs: true,
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        parser: 'boolean',
+        global: true,
+      },
+    },
+    subcommands: {
+      exec,
+    },
+  })
+  expect(run(main, ['node', 'test.js', '--foo', 'bar', 'exec', '-v', '--mode=dev', 'app', '--fast', '-x'])).toBe(0)
+})
+
+test('split unknown args from short clusters and negative numbers', () => {
+  let args: Record<string, unknown> = {}
+  let unknownArgs: string[] = []
+  const main = defineCommand({
+    meta: {
+      name: 'test',
+      allowUnknownArgs: true,
+    },
+    options: {
+      verbose: {
+        type: 'option',
+        parser: 'boolean',
+      },
+      offset: {
+        type: 'option',
+        parser: 'number',
+        allowNegativeNumbers: true,
+      },
+      level: {
+        type: 'option',
+        short: 'l',
+      },
+      target: {
+        type: 'positional',
+      },
+      scale: {
+        type: 'positional',
+        allowNegativeNumbers: true,
+      },
+    },
+    callback: (ctx: Context) => {
+      args = ctx.args
+      unknownArgs = ctx.unknownArgs
+    },
+  })
+
+  run(main, ['node', 'test.js', '-vx', '--offset', '-1', 'app', '-1.5e3'])
+  expect(args).toEqual({ verbose: true, offset: -1, target: 'app', scale: '-1.5e3' })
+  expect(unknownArgs).toEqual(['-x'])
+
+  run(main, ['node', 'test.js', '-xvl', 'debug', 'app', '-inf'])
+  expect(args).toEqual({ verbose: true, level: 'debug', target: 'app' })
+  expect(unknownArgs).toEqual(['-x', '-inf'])
+
+  run(main, ['node', 'test.js', '-vldebug', 'app', '-NaN', 'value', '-infinity'])
+  expect(args).toEqual({ verbose: true, level: 'debug', target: 'app' })
+  expect(unknownArgs).toEqual(['-NaN', 'value', '-infinity'])
+})
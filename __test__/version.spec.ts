@@ -0,0 +1,130 @@
+import { type CommandMeta, type TestResult, defineCommand, runForTest } from 'archons'
+
+function createCommand(meta: CommandMeta) {
+  return defineCommand({
+    meta: {
+      name: 'test',
+      ...meta,
+    },
+    options: {
+      verbose: {
+        action: 'store',
+        help: 'Verbose output',
+      },
+    },
+    subcommands: {
+      sub: defineCommand({
+        meta: {
+          about: 'Subcommand',
+        },
+        options: {},
+        callback: () => {},
+      }),
+      tool: defineCommand({
+        meta: {
+          version: '2.0.0',
+        },
+        options: {},
+        callback: () => {},
+      }),
+    },
+    callback: () => {},
+  })
+}
+
+const longVersion = '1.0.0\ncommit: abc1234\ntarget: x86_64-unknown-linux-gnu'
+
+test('display long version', () => {
+  const cmd = createCommand({ version: '1.0.0', longVersion })
+  let result = runForTest(cmd, ['node', 'test.js', '--version']) as TestResult
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toBe(`test ${longVersion}\n`)
+
+  result = runForTest(cmd, ['node', 'test.js', '-V']) as TestResult
+  expect(result.stdout).toBe('test 1.0.0\n')
+})
+
+test('propagate version to subcommands', () => {
+  let result = runForTest(createCommand({ version: '1.0.0' }), ['node', 'test.js', 'sub', '--version']) as TestResult
+  expect(result.exitCode).toBe(2)
+
+  const cmd = createCommand({ version: '1.0.0', propagateVersion: true })
+  result = runForTest(cmd, ['node', 'test.js', 'sub', '--version']) as TestResult
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toBe('test-sub 1.0.0\n')
+
+  result = runForTest(cmd, ['node', 'test.js', 'tool', '-V']) as TestResult
+  expect(result.stdout).toBe('test-tool 2.0.0\n')
+})
+
+test('customize version flag', () => {
+  const cmd = createCommand({ version: '1.0.0', versionFlag: 'ver', versionShort: 'v', propagateVersion: true })
+  let result = runForTest(cmd, ['node', 'test.js', '-v']) as TestResult
+  expect(result.stdout).toBe('test 1.0.0\n')
+
+  result = runForTest(cmd, ['node', 'test.js', 'sub', '--ver']) as TestResult
+  expect(result.stdout).toBe('test-sub 1.0.0\n')
+
+  result = runForTest(cmd, ['node', 'test.js', '--version']) as TestResult
+  expect(result.exitCode).toBe(2)
+
+  result = runForTest(cmd, ['node', 'test.js', '--help']) as TestResult
+  expect(result.stdout).toContain('-v, --ver')
+  expect(result.stdout).toContain('Print version')
+})
+
+test('disable short version flag', () => {
+  const cmd = createCommand({ version: '1.0.0', versionShort: false })
+  let result = runForTest(cmd, ['node', 'test.js', '-V']) as TestResult
+  expect(result.exitCode).toBe(2)
+
+  result = runForTest(cmd, ['node', 'test.js', '--version']) as TestResult
+  expect(result.stdout).toBe('test 1.0.0\n')
+})
+
+test('print version from callback', async () => {
+  let cmd = createCommand({ version: '1.0.0', propagateVersion: true })
+  cmd.versionCallback = () => 'test 1.0.0 (node)'
+  let result = runForTest(cmd, ['node', 'test.js', '--version']) as TestResult
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toBe('test 1.0.0 (node)\n')
+
+  result = runForTest(cmd, ['node', 'test.js', 'sub', '-V']) as TestResult
+  expect(result.stdout).toBe('test 1.0.0 (node)\n')
+
+  // The subcommand with its own version does not use the callback of the parent
+  result = runForTest(cmd, ['node', 'test.js', 'tool', '-V']) as TestResult
+  expect(result.stdout).toBe('test-tool 2.0.0\n')
+
+  cmd = createCommand({ version: '1.0.0' })
+  cmd.versionCallback = async () => 'test 1.0.0 (async)'
+  result = await runForTest(cmd, ['node', 'test.js', '--version'])
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toBe('test 1.0.0 (async)\n')
+})
+
+test('customize version flag of the multicall applets', () => {
+  const cmd = defineCommand({
+    meta: {
+      name: 'mycli',
+      version: '1.0.0',
+      versionFlag: 'ver',
+      propagateVersion: true,
+      multicall: true,
+    },
+    options: {},
+    subcommands: {
+      'mycli-fmt': defineCommand({
+        meta: {},
+        options: {},
+        callback: () => {},
+      }),
+    },
+  })
+  let result = runForTest(cmd, ['node', '/usr/bin/mycli-fmt', '--ver']) as TestResult
+  expect(result.exitCode).toBe(0)
+  expect(result.stdout).toBe('mycli-fmt 1.0.0\n')
+
+  result = runForTest(cmd, ['node', '/usr/bin/mycli-fmt']) as TestResult
+  expect(result.exitCode).toBe(0)
+})
@@ -0,0 +1,31 @@
+import { defineCommand, run, type Context } from 'archons';
+
+const main = defineCommand({
+  meta: {
+    name: 'fail',
+    about: 'Validate a manifest file',
+  },
+  options: {
+    manifest: {
+      type: 'positional',
+      required: true,
+      help: 'Path of the manifest',
+    },
+    code: {
+      type: 'option',
+      parser: 'number',
+      help: 'Exit code of the failure',
+    },
+  },
+  callback: (ctx: Context) => {
+    if (ctx.args.manifest === 'kind') {
+      ctx.error('invalid-value', `invalid manifest '${ctx.args.manifest}'`);
+    }
+    ctx.fail('manifest is not valid', {
+      exitCode: ctx.args.code,
+      hint: 'run `fail init` to create one',
+    });
+  }
+})
+
+run(main)
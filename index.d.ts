@@ -10,6 +10,16 @@
  * @returns {Command}
  */
 export declare function defineCommand(options: Command): Command
+/** Options of `run` */
+export interface RunOptions {
+  /**
+   * Prompt for the missing required options, and the options with `prompt`,
+   * before running the command instead of failing
+   *
+   * Only available when stdin is a TTY.
+   */
+  interactive?: boolean
+}
 /**
  * Run command
  *
@@ -20,11 +30,73 @@ export declare function defineCommand(options: Command): Command
  * For example, if you are running a script `index.js` in the current directory with
  * a flag `--foo`, you should pass `["node", "index.js", "--foo"]` as `args`.
  *
+ * **NOTE**: The exit code of the run is returned instead of exiting the process,
+ * which is the number returned by the callback, the code given to `ctx.exit`,
+ * the code of a parsing error or `ctx.fail`, or the code mapped by `exitCodes`.
+ * A non-zero exit code is also set to `process.exitCode`.
+ *
+ * **NOTE**: If the callback or any lifecycle hook returns a `Promise`,
+ * a `Promise` of the exit code is returned instead.
+ *
  * @param cmd Command object
  * @param args Run with given arguments
- * @returns {void}
+ * @param options Run options
+ * @returns {number | Promise<number>}
+ */
+export declare function run(cmd: Command, args?: string[], options?: RunOptions): number | Promise<number>
+/**
+ * Run command with the arguments of a command line
+ *
+ * The line is split with the POSIX shell quoting rules, e.g.
+ * `deploy --env 'prod eu' --yes` runs `deploy` with `--env` of `prod eu`,
+ * and no expansion is performed. Unlike `run`, the line does not include
+ * the nodejs executable and script name, while the main command is still
+ * named by the script if `meta.name` is not set.
+ *
+ * @param cmd Command object
+ * @param line Command line
+ * @returns {number | Promise<number>}
+ */
+export declare function runString(cmd: Command, line: string): number | Promise<number>
+/**
+ * Split a command line into arguments with the POSIX shell quoting rules
+ *
+ * Quotes and escapes are resolved, while variables, globs and other
+ * expansions are kept as is.
+ *
+ * @param line Command line
+ * @returns {string[]}
  */
-export declare function run(cmd: Command, args?: string[]): void
+export declare function parseString(line: string): Array<string>
+/**
+ * Command lifecycle hooks
+ *
+ * Hooks receive the same `Context` as the command callback and may return
+ * a `Promise`, which is awaited before the next hook is called.
+ */
+export interface CommandHooks {
+  /**
+   * Called before the command callback
+   *
+   * The hooks of the matched command and its parent commands are called
+   * from the root command to the matched subcommand. Throw an error to abort
+   * the run, or return `false` to skip the remaining hooks and the callback.
+   */
+  preRun?: (ctx: Context) => void | boolean | Promise<void | boolean>
+  /**
+   * Called after the command callback succeeds
+   *
+   * The hooks are called from the matched subcommand to the root command.
+   */
+  postRun?: (ctx: Context) => void | Promise<void>
+  /**
+   * Called when a hook or the command callback throws
+   *
+   * The hooks are called from the matched subcommand to the root command,
+   * and the error is rethrown after all the hooks are called.
+   */
+  onError?: (ctx: Context, error: unknown) => void | Promise<void>
+}
 export interface SelectConfig {
   helpMessage?: string
   pageSize?: number
@@ -90,6 +162,16 @@ export interface StringValidatorResult {
   errMsg?: string
 }
 export declare function password(prompt: string, config?: PasswordConfig | undefined | null): string
+/**
+ * Get the built-in messages of the locale
+ *
+ * The messages are keyed by their ids, which can be overridden by the
+ * `messages` of the command metadata.
+ *
+ * @param locale Locale of the messages, defaults to `en`
+ * @returns {Record<string, string>}
+ */
+export declare function getMessages(locale?: 'en' | 'zh-CN'): Record<string, string>
 /**
  * Creates a new progress bar with the specified total number of steps.
  *
@@ -110,6 +192,132 @@ export declare function createProgressBar(total: number): ProgressBar
  * A new `ProgressBar` instance with a spinner style.
  */
 export declare function createSpinner(): ProgressBar
+/** Options of `repl` */
+export interface ReplOptions {
+  /** Prompt displayed before each line, defaults to `<name>>` */
+  prompt?: string
+  /**
+   * Path of the history file, the lines are loaded from the file on start
+   * and appended to the file after they are entered
+   */
+  history?: string
+}
+/**
+ * Start an interactive shell over the command tree
+ *
+ * Each line is split with the shell quoting rules and run as the arguments
+ * of the command without the binary name, e.g. `dev --port 3000` runs the
+ * `dev` subcommand. Parse errors and errors thrown by the lines are printed
+ * instead of ending the REPL.
+ *
+ * The subcommands and options are completed with `tab`, and the previous
+ * lines are suggested while typing. The built-in `help [command]` prints the
+ * help of the command, and `exit` or `Ctrl-C` ends the REPL.
+ *
+ * **NOTE**: If a line returns a `Promise`, the REPL continues after it is
+ * settled and a `Promise` is returned instead.
+ *
+ * @param cmd Command object
+ * @param options REPL options
+ * @returns {void | Promise<void>}
+ */
+export declare function repl(cmd: Command, options?: ReplOptions): void | Promise<void>
+/** Options of `runForTest` */
+export interface TestOptions {
+  /** Environment variables set during the run */
+  env?: Record<string, string>
+  /**
+   * Input of the prompts, one line for each prompt after the `answers`
+   * are exhausted
+   */
+  stdin?: string
+  /**
+   * Answers of the prompts in order
+   *
+   * A string for the text and select prompts, a boolean for the confirm
+   * prompts and a string array for the checkbox prompts.
+   */
+  answers?: Array<string | boolean | string[]>
+  /**
+   * Run in the interactive mode, the missing options are prompted and
+   * answered by `answers` and `stdin`
+   */
+  interactive?: boolean
+}
+/** Result of `runForTest` */
+export interface TestResult {
+  /** Captured stdout */
+  stdout: string
+  /** Captured stderr */
+  stderr: string
+  /** Exit code of the run */
+  exitCode: number
+  /** Value returned by the callback */
+  result: any
+}
+/**
+ * Run command in process for testing
+ *
+ * The output of clap, the callback and the hooks is captured instead of
+ * being written to stdout and stderr, progress bars are hidden and the
+ * prompts are answered by `answers` and `stdin` of the options.
+ *
+ * **NOTE**: The runs can not be nested or overlapped.
+ *
+ * @param cmd Command object
+ * @param args Run with given arguments, starting with the executable and
+ * the script name, e.g. `['node', 'cli.js']`
+ * @param options Test options
+ * @returns {TestResult | Promise<TestResult>}
+ */
+export declare function runForTest(cmd: Command, args: string[], options?: TestOptions): TestResult | Promise<TestResult>
+/** Options of `ctx.fail` */
+export interface FailOptions {
+  /** Exit code of the process, defaults to `2` as the usage errors */
+  exitCode?: number
+  /** Hint displayed as a tip below the error message */
+  hint?: string
+}
+/** Style of a part of the output */
+export interface ThemeStyle {
+  /**
+   * Foreground color
+   *
+   * One of the ANSI color names, e.g. `red` or `bright-red`, an index of
+   * the 256-color palette, e.g. `208`, or a hex RGB color, e.g. `#ff8800`.
+   */
+  color?: string
+  /** Background color, in the same format as `color` */
+  background?: string
+  /** Text effects */
+  effects?: Array<'bold' | 'dimmed' | 'italic' | 'underline' | 'blink' | 'invert' | 'hidden' | 'strikethrough'>
+}
+/**
+ * Theme of the output
+ *
+ * The parts which are not specified keep the styles of the preset.
+ */
+export interface Theme {
+  /** Preset which the theme is based on, defaults to `default` */
+  preset?: 'default' | 'styled' | 'plain'
+  /** Section headers, e.g. `Options:`, and the prompt prefixes */
+  header?: ThemeStyle
+  /** Usage heading, e.g. `Usage:` */
+  usage?: ThemeStyle
+  /**
+   * Literal text, e.g. the flags and the subcommands, the highlighted choices
+   * of the prompts and the progress bars
+   */
+  literal?: ThemeStyle
+  /** Placeholders, e.g. `<NAME>`, and the defaults and hints of the prompts */
+  placeholder?: ThemeStyle
+  /** Error heading, e.g. `error:` */
+  error?: ThemeStyle
+  /** Valid values in the error messages, and the answers of the prompts */
+  valid?: ThemeStyle
+  /** Invalid values in the error messages */
+  invalid?: ThemeStyle
+}
 /** Command metadata */
 export interface CommandMeta {
   /**
@@ -130,24 +338,207 @@ export interface CommandMeta {
    * call the command with the `--version` flag or `-V` option.
    */
   version?: string
+  /**
+   * CLI long version
+   *
+   * Displayed instead of `version` when the command is called with the long
+   * version flag, e.g. `--version`, while `-V` still displays `version`.
+   * This is useful for multi-line build information, like the commit, the
+   * build date and the target.
+   *
+   * The version flag is enabled if either `version` or `longVersion` is provided.
+   */
+  longVersion?: string
+  /**
+   * Propagate the version to the subcommands
+   *
+   * If true, every subcommand accepts the version flag and displays the
+   * version of the command, unless the subcommand provides its own version.
+   */
+  propagateVersion?: boolean
+  /**
+   * Long name of the version flag
+   *
+   * Inherited by the subcommands. Defaults to `version`.
+   */
+  versionFlag?: string
+  /**
+   * Short name of the version flag
+   *
+   * Pass `false` to disable the short flag. Only the first character is used.
+   * Inherited by the subcommands. Defaults to `V`.
+   */
+  versionShort?: string | false
   /**
    * Command description
    *
    * Command description will be displayed in the help output.
    */
   about?: string
+  /**
+   * Long command description
+   *
+   * Displayed instead of `about` in the long help output, e.g. `--help`.
+   */
+  longAbout?: string
+  /**
+   * Command author
+   *
+   * Only displayed with the `{author}` placeholder of `help_template`.
+   */
+  author?: string
+  /** Text displayed before the help output */
+  beforeHelp?: string
+  /** Text displayed before the long help output, defaults to `before_help` */
+  beforeLongHelp?: string
+  /** Text displayed after the help output */
+  afterHelp?: string
+  /** Text displayed after the long help output, defaults to `after_help` */
+  afterLongHelp?: string
+  /**
+   * Usage examples
+   *
+   * Displayed as the `Examples` section after the options, one example
+   * per line, e.g. `["mycli deploy --env prod", "mycli deploy --dry-run"]`.
+   */
+  examples?: Array<string>
+  /**
+   * Custom help template
+   *
+   * The placeholders are `{name}`, `{version}`, `{author}`, `{about}`,
+   * `{usage-heading}`, `{usage}`, `{all-args}`, `{options}`, `{positionals}`,
+   * `{subcommands}`, `{tab}`, `{before-help}` and `{after-help}`, e.g.
+   * `"{about}\n\n{usage-heading} {usage}\n\n{all-args}{after-help}"`.
+   */
+  helpTemplate?: string
+  /** Custom usage string, which overrides the generated usage */
+  usage?: string
+  /** Display the help of the options on the line after their flags */
+  nextLineHelp?: boolean
   /**
    * Enable styled mode
    *
-   * Determines whether the CLI output should be displayed in the styled format.
+   * Determines whether the CLI output should be displayed in the styled format,
+   * the same as the `styled` theme. Ignored if `theme` is specified.
    */
   styled?: boolean
+  /**
+   * Theme of the help, error, prompt and progress bar output
+   *
+   * Either the name of a preset or the styles of each part of the output:
+   *
+   * - `default`: Bold and underlined headers, the default of clap
+   * - `styled`: Green headers and cyan flags
+   * - `plain`: No styles
+   *
+   * A subcommand can only specify its own theme if none of its parents do,
+   * otherwise the theme is inherited.
+   */
+  theme?: 'default' | 'styled' | 'plain' | Theme
+  /**
+   * When to use colors in the output
+   *
+   * - `auto`: Use colors if the output is a TTY, unless `NO_COLOR` is set to
+   *   a non-empty value, or if `FORCE_COLOR` is set to a value other than `0`
+   * - `always`: Always use colors
+   * - `never`: Never use colors
+   *
+   * Inherited by the subcommands. Defaults to `auto`.
+   */
+  color?: 'auto' | 'always' | 'never'
+  /**
+   * Locale of the built-in messages
+   *
+   * Translates the help headings, the descriptions of `--help`, `--version`
+   * and the `help` subcommand, the error messages of clap and the default
+   * help messages of the prompts. Only the locale of the main command is used,
+   * and the prompts called after the run are not translated.
+   *
+   * Defaults to the messages of clap and inquire, which are in English.
+   */
+  locale?: 'en' | 'zh-CN'
+  /**
+   * Overrides of the built-in messages
+   *
+   * Keyed by the message ids, see `getMessages` for the ids and the messages
+   * of the locales, e.g. `{ 'usage-heading': 'USAGE' }`. The placeholders
+   * of the messages, e.g. `{arg}`, are replaced by the values. Only the
+   * messages of the main command are used.
+   */
+  messages?: Record<string, string>
   /**
    * Subcommand required
    *
    * If true, the command will fail if no subcommand is provided.
    */
   subcommandRequired?: boolean
+  /**
+   * Multicall mode
+   *
+   * If true, the executable name (without the extension) selects the
+   * subcommand to run, like busybox. For example, with the `mycli-fmt` bin
+   * linked to the same script, `mycli-fmt --check` runs the `mycli-fmt`
+   * subcommand with `--check`, and its help is displayed as `mycli-fmt`.
+   *
+   * To run the applets from the canonical name as well, e.g. `mycli fmt`,
+   * add a subcommand with the canonical name which contains the applets.
+   *
+   * The multicall command can not have options, a definition error is thrown
+   * if it has.
+   */
+  multicall?: boolean
+  /**
+   * Allow unknown arguments
+   *
+   * If true, the options which are not defined by the command (and their values)
+   * will be collected into `ctx.unknownArgs` in their original order instead of
+   * failing, and the known options will be parsed as usual. This is useful for
+   * wrapper CLIs that forward the unknown options to the wrapped tool.
+   *
+   * An unknown option takes the following argument as its value unless it starts
+   * with `-` or is a subcommand name, so prefer `--option=value` if it is a flag
+   * followed by a positional argument.
+   *
+   * Subcommands inherit this setting unless it is explicitly disabled.
+   */
+  allowUnknownArgs?: boolean
+  /**
+   * Naming policy for the option flags
+   *
+   * - `verbatim`: Use the option keys as the long flags, e.g. `--dryRun`
+   * - `kebab-case`: Derive kebab-case long flags from the option keys,
+   *   e.g. `--dry-run` for `dryRun`
+   *
+   * The keys of `ctx.args` are always the option keys. `conflicts_with` accepts
+   * either the option keys or the renamed names. Explicit `long` names are not
+   * renamed.
+   *
+   * Subcommands inherit this setting unless it is explicitly specified.
+   *
+   * Defaults to `verbatim`.
+   */
+  renameAll?: 'verbatim' | 'kebab-case'
+  /**
+   * Exit codes of the errors
+   *
+   * Maps the errors thrown by the hooks or the callback to the exit code of
+   * `run`, keyed by the `code` of the error or the name of its class, e.g.
+   * `{ ENOENT: 3, ValidationError: 4 }`. The mapped errors are printed to
+   * stderr instead of being rethrown. Inherited by the subcommands.
+   */
+  exitCodes?: Record<string, number>
+  /**
+   * Interactive menu of the subcommands
+   *
+   * If true and the command is run without a subcommand while it has no
+   * callback, a select of the subcommands (with their `about`) is displayed
+   * instead of failing, until a command with a callback is picked. The
+   * picked command is run as if it had been typed, and its missing required
+   * options are prompted. Only available when stdin is a TTY.
+   *
+   * Inherited by the subcommands.
+   */
+  interactiveMenu?: boolean
 }
 export interface CommandOption {
   /**
@@ -175,23 +566,30 @@ export interface CommandOption {
    * - `count`: Count how many times a flag occurs
    * - `store`: Store the value as a boolean flag
    * - `store_false`: Store the value as a boolean flag with opposite meaning
+   * - `map`: Collect `KEY=VALUE` pairs into an object without prototype
    *
    * Defaults to `set` if not specified.
    */
-  action?: 'set' | 'append' | 'count' | 'store' | 'store_false'
+  action?: 'set' | 'append' | 'count' | 'store' | 'store_false' | 'map'
   /**
    * Short option name
    *
    * This is a single character that can be used to represent the option
    * in the command line. For example, `-v` for the `--verbose` option.
-   * Panics if the length of the string is empty. If the size of string
+   * Fails if the length of the string is empty. If the size of string
    * is greater than 1, the first character will be used as the short option.
+   * Pass `false` to disable the short option name.
+   *
+   * Explicit short option names must not collide with each other, the built-in
+   * `-h` and `-V` flags or the global options of the parent commands.
    *
    * This option will be ignored if option `type` is not `option`.
    *
-   * Defaults to the first character of the long option name.
+   * Defaults to the first character of the long option name if it is not taken
+   * by other options (checked in alphabetical order of the option keys), or no
+   * short option name for namespaced options.
    */
-  short?: string
+  short?: string | boolean
   /**
    * Long option name
    *
@@ -232,6 +630,34 @@ export interface CommandOption {
     | 'email'
   /** Option description */
   help?: string
+  /**
+   * Long option description
+   *
+   * Displayed instead of `help` in the long help output, e.g. `--help`.
+   */
+  longHelp?: string
+  /** Placeholder of the value in the help output, e.g. `FILE` for `--out <FILE>` */
+  valueName?: string
+  /**
+   * Placeholders of the values in the help output, one for each value,
+   * e.g. `["KEY", "VALUE"]` for `--set <KEY> <VALUE>`
+   */
+  valueNames?: Array<string>
+  /** Heading of the section which the option is grouped into in the help output */
+  helpHeading?: string
+  /** Order of the option in the help output, the lower the earlier */
+  displayOrder?: number
+  /** Hide the possible values of `choices` in the help output */
+  hidePossibleValues?: boolean
+  /** Environment variable which the value is read from when the option is not given */
+  env?: string
+  /** Hide the environment variable and its value in the help output */
+  hideEnv?: boolean
+  /**
+   * Hide the option in the short help output, e.g. `-h`, but keep it in the
+   * long help output, e.g. `--help`
+   */
+  hideShortHelp?: boolean
   /**
    * Required argument
    *
@@ -293,6 +719,105 @@ export interface CommandOption {
    * in the help text.
    */
   hideDefaultValue?: boolean
+  /**
+   * Last positional argument
+   *
+   * This argument can only be provided after `--`, and all the values
+   * after `--` will be collected verbatim. For example, `exec -- npm test --watch`.
+   *
+   * Defaults `num_args` to `1..` if not specified.
+   */
+  last?: boolean
+  /**
+   * Trailing variable argument
+   *
+   * This is a "var arg" and everything that follows should be captured by it,
+   * as if the user had used a `--`. Only valid for the last positional argument.
+   *
+   * Defaults `num_args` to `1..` if not specified.
+   */
+  trailingVarArg?: boolean
+  /**
+   * Allow values which start with a leading hyphen (`-`)
+   *
+   * To start the trailing var arg on unknown flags, set this option along
+   * with `trailing_var_arg`.
+   */
+  allowHyphenValues?: boolean
+  /**
+   * Allow negative numbers to pass as values
+   *
+   * This is similar to `allow_hyphen_values` except that it only allows numbers,
+   * all other undefined leading hyphens will fail to parse.
+   */
+  allowNegativeNumbers?: boolean
+  /**
+   * Generate a paired `--no-<long>` flag for the boolean flag
+   *
+   * The last one of the pair given in the command line wins, and a given
+   * negation beats the value of `env` and `default`. This is useful to turn
+   * off a flag enabled by default, e.g. `--color` and `--no-color`. Displayed as `--[no-]color` in help output.
+   *
+   * Throws a definition error if the option is not a boolean flag.
+   */
+  negatable?: boolean
+  /**
+   * Separator between the key and the value of `map` options
+   *
+   * For example, `:` for `--header 'X-A: b'`. Whitespaces around the key
+   * and the value are trimmed, and the key must not be empty.
+   *
+   * Defaults to `=`.
+   */
+  mapSeparator?: string
+  /**
+   * How to handle duplicate keys of `map` options
+   *
+   * - `error`: Fail the parsing with a clap error if a key is given more than once
+   * - `last`: The last value wins
+   * - `array`: Collect all the values of each key into an array
+   *
+   * Defaults to `last`.
+   */
+  mapDuplicateKey?: 'error' | 'last' | 'array'
+  /**
+   * Split the values of the argument by the given delimiter
+   *
+   * For example, `,` makes `--targets a,b,c` behave like
+   * `--targets a --targets b --targets c`, and each value is parsed
+   * by the specified `parser`. Only the first character is used.
+   */
+  valueDelimiter?: string
+  /**
+   * Possible values of the argument
+   *
+   * The values not in the choices are rejected, and the choices are displayed
+   * in the help message. Only available for the `string` parser, and not
+   * for the `map` action.
+   */
+  choices?: Array<string>
+  /**
+   * Sensitive argument
+   *
+   * The default value is hidden in the help message, and the argument is
+   * prompted as a password in the interactive mode.
+   */
+  sensitive?: boolean
+  /**
+   * Prompt for the argument in the interactive mode
+   *
+   * If the argument is not given in the command line, it is prompted with
+   * the given message, or with the `help` (or the option name) if `true`.
+   * The required arguments are prompted by default, pass `false` to fail
+   * without them as usual.
+   *
+   * Booleans are prompted with a confirm, `choices` with a select (or a
+   * checkbox for multiple values), `sensitive` arguments with a password,
+   * and others with a text input, where multiple values are split with the
+   * shell quoting rules. `count` and `map` arguments are never prompted, nor
+   * the arguments conflicting with the given or prompted ones.
+   */
+  prompt?: string | boolean
 }
 /**
  * Command definition
@@ -302,8 +827,40 @@ export interface CommandOption {
  */
 export interface Command {
   meta: CommandMeta
+  /**
+   * Command options
+   *
+   * Dotted option names are namespaced, e.g. `db.host` and `db.port` are
+   * parsed from `--db.host` and `--db.port` into `ctx.args.db.host` and
+   * `ctx.args.db.port`, the same as a config file keyed by `db`. Namespaced
+   * options are grouped under a heading per namespace in help output, and a
+   * namespace can not be an option itself, e.g. `db` with `db.host`.
+   */
   options: Record<string, CommandOption>
-  callback?: (ctx: Context) => void
+  callback?: (ctx: Context) => void | number | Promise<void | number>
+  /**
+   * Lifecycle hooks
+   *
+   * Hooks of parent commands also apply to their subcommands.
+   */
+  hooks?: CommandHooks
+  /**
+   * State provider
+   *
+   * Called before the `preRun` hook of this command, the properties of the
+   * returned object are assigned to `ctx.state`, e.g. a database connection
+   * opened by the main command for its subcommands.
+   */
+  provide?: (ctx: Context) => Record<string, any> | void | Promise<Record<string, any> | void>
+  /**
+   * Version callback
+   *
+   * Called instead of displaying the static version when the version flag is
+   * given, the returned string is printed to stdout, e.g. the versions of the
+   * runtime or the plugins loaded at runtime. The callback of the deepest
+   * matched command is used. The command must still provide a `version`.
+   */
+  versionCallback?: () => string | void | Promise<string | void>
   subcommands?: Record<string, Command>
 }
 export declare class ProgressBar {
@@ -342,6 +899,26 @@ export declare class Context {
    * The raw arguments parsed by command line or manually given.
    */
   rawArgs: string[]
+  /**
+   * Unknown arguments
+   *
+   * The arguments which are not defined by the command, in their original order.
+   * Only collected when `allow_unknown_args` is enabled in the command metadata.
+   */
+  unknownArgs: string[]
+  /**
+   * Matched command path
+   *
+   * The names of the matched commands from the main command to the current
+   * subcommand, e.g. `['mycli', 'remote', 'add']`.
+   */
+  commandPath: string[]
+  /**
+   * Binary name
+   *
+   * The file name of the executed script, as displayed in the usage.
+   */
+  binName: string
   ask(prompt: string, config?: InputConfig | undefined | null): string
   confirm(prompt: string, config?: ConfirmConfig | undefined | null): boolean
   createProgressBar(total: number): ProgressBar
@@ -354,12 +931,78 @@ export declare class Context {
    *
    * @param args - Parsed arguments
    * @param raw_args - Raw arguments
+   * @param state - Shared state
    */
-  constructor(args: Record<string, any>, raw_args: string[])
+  constructor(args: Record<string, any>, raw_args: string[], state?: Record<string, any>)
   /** Get the parsed arguments */
   get args(): Record<string, any>
+  /** Get the metadata of the matched command */
+  get command(): CommandMeta
+  /**
+   * Get the context of the parent command
+   *
+   * The parent context only contains the arguments of the parent command.
+   */
+  get parent(): Context | null
+  /** Get the shared state */
+  get state(): Record<string, any>
   /** Get the raw arguments */
   getRawArgs(): string[]
-  /** Get the argument value by specified key */
-  get(key: string): any
+  /** Get the unknown arguments */
+  getUnknownArgs(): string[]
+  /** Get the help message of the matched command */
+  help(): string
+  /** Get the usage of the matched command */
+  usage(): string
+  /**
+   * Render the help message of the matched command
+   *
+   * @param long - Render the long help message, the same as `--help`
+   */
+  renderHelp(long?: boolean | undefined | null): string
+  /**
+   * Print the help message of the matched command to stdout
+   *
+   * @param long - Print the long help message, the same as `--help`
+   */
+  printHelp(long?: boolean | undefined | null): void
+  /**
+   * Raise a clap error of the matched command and end the run
+   *
+   * The error is printed to stderr the same way as the parsing errors,
+   * with the usage and exit code of clap, after the `onError` hooks are called.
+   * Throws a type error if the kind is not supported.
+   *
+   * @param kind - Error kind
+   * @param message - Error message
+   */
+  error(kind: 'invalid-value' | 'unknown-argument' | 'invalid-subcommand' | 'no-equals' | 'value-validation' | 'too-many-values' | 'too-few-values' | 'wrong-number-of-values' | 'argument-conflict' | 'missing-required-argument' | 'missing-subcommand' | 'io' | 'format', message: string): never
+  /**
+   * Fail the matched command with a custom message and end the run
+   *
+   * @param message - Error message
+   * @param options - Exit code and hint of the failure
+   */
+  fail(message: string, options?: FailOptions | undefined | null): never
+  /**
+   * End the run with the given exit code
+   *
+   * The remaining hooks are skipped and `run` returns the exit code.
+   *
+   * @param code - Exit code, defaults to `0`
+   */
+  exit(code?: number | undefined | null): never
+  /**
+   * Get the argument value by specified key
+   *
+   * Dotted keys are resolved in the nested namespaces, e.g. `db.host`.
+   */
+  get<T = any>(key: string): T
+  /**
+   * Get the value of specified key in the shared state, which is written
+   * by `set` or the state providers
+   */
+  getState<T = any>(key: string): T
+  /** Set the value of specified key in the shared state */
+  set<T = any>(key: string, value: T): void
 }
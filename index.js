@@ -287,25 +287,35 @@ if (!nativeBinding) {
 const {
   defineCommand,
   run,
+  runString,
+  parseString,
   select,
   checkbox,
   input,
   confirm,
   password,
+  getMessages,
   ProgressBar,
   createProgressBar,
   createSpinner,
+  repl,
+  runForTest,
   Context,
 } = nativeBinding
 
 module.exports.defineCommand = defineCommand
 module.exports.run = run
+module.exports.runString = runString
+module.exports.parseString = parseString
 module.exports.select = select
 module.exports.checkbox = checkbox
 module.exports.input = input
 module.exports.confirm = confirm
 module.exports.password = password
+module.exports.getMessages = getMessages
 module.exports.ProgressBar = ProgressBar
 module.exports.createProgressBar = createProgressBar
 module.exports.createSpinner = createSpinner
+module.exports.repl = repl
+module.exports.runForTest = runForTest
 module.exports.Context = Context
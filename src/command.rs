@@ -1,9 +1,16 @@
-use napi::{Env, Result};
-use napi_derive::napi;
+use napi::{CallContext, Env, JsFunction, JsObject, JsUnknown, Result, ValueType};
+use napi_derive::{js_function, napi};
 
-use crate::resolver::{resolve_command, resolve_option_args};
+use crate::hooks::{exit_with, is_thenable, then};
+use crate::inquire::{reset_prompt_theme, select_labeled};
+use crate::locale::{localize_command, message, reset_locale, set_locale};
+use crate::resolver::{
+  display_negatable_flags, find_subcommand_def, resolve_command, resolve_option_args,
+  resolve_unknown_args, Inherited,
+};
+use crate::testing::{can_prompt, print_clap_error, write, Stream};
 use crate::types::Command;
-use crate::utils::parse_arguments;
+use crate::utils::{parse_arguments, resolve_bin_name, split_words};
 
 /// Define a command functionally
 ///
@@ -14,6 +21,17 @@ pub fn define_command(options: Command) -> Command {
   options
 }
 
+/// Options of `run`
+#[napi(object)]
+#[derive(Default)]
+pub struct RunOptions {
+  /// Prompt for the missing required options, and the options with `prompt`,
+  /// before running the command instead of failing
+  ///
+  /// Only available when stdin is a TTY.
+  pub interactive: Option<bool>,
+}
+
 /// Run command
 ///
 /// **NOTE**: If the given `args` is empty, it will use `process.argv`
@@ -23,14 +41,314 @@ pub fn define_command(options: Command) -> Command {
 /// For example, if you are running a script `index.js` in the current directory with
 /// a flag `--foo`, you should pass `["node", "index.js", "--foo"]` as `args`.
 ///
+/// **NOTE**: The exit code of the run is returned instead of exiting the process,
+/// which is the number returned by the callback, the code given to `ctx.exit`,
+/// the code of a parsing error or `ctx.fail`, or the code mapped by `exitCodes`.
+/// A non-zero exit code is also set to `process.exitCode`.
+///
+/// **NOTE**: If the callback or any lifecycle hook returns a `Promise`,
+/// a `Promise` of the exit code is returned instead.
+///
 /// @param cmd Command object
 /// @param args Run with given arguments
-/// @returns {void}
-#[napi(ts_args_type = "cmd: Command, args?: string[]")]
-pub fn run(env: Env, cmd: Command, argv: Option<Vec<String>>) -> Result<()> {
+/// @param options Run options
+/// @returns {number | Promise<number>}
+#[napi(
+  ts_args_type = "cmd: Command, args?: string[], options?: RunOptions",
+  ts_return_type = "number | Promise<number>"
+)]
+pub fn run(
+  env: Env,
+  cmd: Command,
+  argv: Option<Vec<String>>,
+  options: Option<RunOptions>,
+) -> Result<JsUnknown> {
   let raw_args = resolve_option_args(env, argv)?;
-  let clap = resolve_command(clap::Command::default(), Default::default(), &cmd);
-  let matches = clap.clone().get_matches_from(&raw_args);
+  let options = options.unwrap_or_default();
+  set_locale(&cmd.meta)?;
+  // Prompts are only available when the user can answer them
+  let interactive = options.interactive.unwrap_or(false) && can_prompt();
+  let inherited = Inherited {
+    interactive,
+    ..Default::default()
+  };
+  let result = resolve_command(
+    clap::Command::default(),
+    Default::default(),
+    &cmd,
+    &inherited,
+  )
+  .and_then(|clap| execute(env, &clap, cmd, raw_args, interactive, None));
+  settle_run(env, result)
+}
+
+/// Run command with the arguments of a command line
+///
+/// The line is split with the POSIX shell quoting rules, e.g.
+/// `deploy --env 'prod eu' --yes` runs `deploy` with `--env` of `prod eu`,
+/// and no expansion is performed. Unlike `run`, the line does not include
+/// the nodejs executable and script name, while the main command is still
+/// named by the script if `meta.name` is not set.
+///
+/// @param cmd Command object
+/// @param line Command line
+/// @returns {number | Promise<number>}
+#[napi(
+  ts_args_type = "cmd: Command, line: string",
+  ts_return_type = "number | Promise<number>"
+)]
+pub fn run_string(env: Env, cmd: Command, line: String) -> Result<JsUnknown> {
+  // The main command is named by the script the same as `run` without `meta.name`
+  let name = resolve_bin_name(&resolve_option_args(env, None)?).unwrap_or_default();
+  set_locale(&cmd.meta)?;
+  let result =
+    resolve_command(clap::Command::default(), name, &cmd, &Default::default()).and_then(|clap| {
+      let clap = clap.no_binary_name(true);
+      let mut raw_args = vec![clap.get_name().to_string()];
+      raw_args.extend(split_words(&line)?);
+      execute(env, &clap, cmd, raw_args, false, None)
+    });
+  settle_run(env, result)
+}
+
+/// Reset the messages and the prompt theme of the run once it is settled,
+/// so that they are not used by the prompts called after the run
+pub(crate) fn settle_run(env: Env, result: Result<JsUnknown>) -> Result<JsUnknown> {
+  match result {
+    Ok(value) if is_thenable(&value)? => then(
+      value,
+      env.create_function("settle", settle_fulfilled)?,
+      Some(env.create_function("settle", settle_rejected)?),
+    ),
+    result => {
+      reset_run();
+      result
+    }
+  }
+}
+
+fn reset_run() {
+  reset_locale();
+  reset_prompt_theme();
+}
+
+/// Continuation of the run after it is fulfilled, with the value of the run
+#[js_function(1)]
+fn settle_fulfilled(ctx: CallContext) -> Result<JsUnknown> {
+  reset_run();
+  ctx.get::<JsUnknown>(0)
+}
+
+/// Continuation of the run after it is rejected, with the error of the run
+#[js_function(1)]
+fn settle_rejected(ctx: CallContext) -> Result<JsUnknown> {
+  reset_run();
+  ctx.env.throw(ctx.get::<JsUnknown>(0)?)?;
+  Err(napi::Error::new(napi::Status::PendingException, ""))
+}
+
+/// Split a command line into arguments with the POSIX shell quoting rules
+///
+/// Quotes and escapes are resolved, while variables, globs and other
+/// expansions are kept as is.
+///
+/// @param line Command line
+/// @returns {string[]}
+#[napi]
+pub fn parse_string(line: String) -> Result<Vec<String>> {
+  split_words(&line)
+}
+
+/// Pick the subcommands to run from the interactive menu, if the matched
+/// command has no callback and the menu is enabled
+fn pick_subcommands(cmd: &Command, matches: &clap::ArgMatches) -> Result<Vec<String>> {
+  let mut current = cmd;
+  let mut matches = matches;
+  let mut menu = cmd.meta.interactive_menu.unwrap_or(false);
+  while let Some((name, sub_matches)) = matches.subcommand() {
+    let Some(sub_cmd) = find_subcommand_def(current, name) else {
+      break;
+    };
+    current = sub_cmd;
+    matches = sub_matches;
+    menu = current.meta.interactive_menu.unwrap_or(menu);
+  }
+  if !menu || !can_prompt() {
+    return Ok(Vec::new());
+  }
+
+  let mut path = Vec::new();
+  while current.callback.is_none() {
+    let Some(subcommands) = current.subcommands.as_ref().filter(|sub| !sub.is_empty()) else {
+      break;
+    };
+    let mut names = subcommands
+      .iter()
+      .map(|(key, sub_cmd)| sub_cmd.meta.name.clone().unwrap_or_else(|| key.clone()))
+      .collect::<Vec<_>>();
+    names.sort();
+    let width = names
+      .iter()
+      .map(|name| name.len())
+      .max()
+      .unwrap_or_default();
+    let labels = names
+      .iter()
+      .map(|name| {
+        let about = find_subcommand_def(current, name)
+          .and_then(|sub_cmd| sub_cmd.meta.about.as_deref())
+          .unwrap_or_default();
+        format!("{:width$}  {}", name, about).trim_end().to_string()
+      })
+      .collect::<Vec<_>>();
+    let name = select_labeled(message("select-command"), &names, labels)?;
+    current = find_subcommand_def(current, &name).unwrap();
+    path.push(name);
+  }
+  Ok(path)
+}
+
+/// Find the version callback of the deepest matched command which provides a version
+///
+/// The subcommands without their own versions display the propagated version,
+/// so the version callback of their parent commands is used instead.
+fn find_version_callback<'a>(
+  clap: &clap::Command,
+  cmd: &'a Command,
+  known_args: &[String],
+) -> Option<&'a JsFunction> {
+  let mut clap = clap.clone();
+  clap.build();
+
+  let mut current = &clap;
+  let mut current_def = cmd;
+  let mut callback = cmd.version_callback.as_ref();
+  let skip = usize::from(!clap.is_no_binary_name_set());
+  for token in known_args
+    .iter()
+    .skip(skip)
+    .take_while(|token| *token != "--")
+  {
+    let Some(sub_command) = current.find_subcommand(token) else {
+      continue;
+    };
+    let Some(sub_def) = find_subcommand_def(current_def, sub_command.get_name()) else {
+      break;
+    };
+    if sub_def.version_callback.is_some()
+      || sub_def.meta.version.is_some()
+      || sub_def.meta.long_version.is_some()
+    {
+      callback = sub_def.version_callback.as_ref();
+    }
+    current = sub_command;
+    current_def = sub_def;
+  }
+  callback
+}
+
+/// Print the version returned by the version callback
+fn print_version(env: Env, version: JsUnknown) -> Result<JsUnknown> {
+  if version.get_type()? == ValueType::String {
+    let version = version.coerce_to_string()?.into_utf8()?.into_owned()?;
+    match version.ends_with('\n') {
+      true => write(Stream::Stdout, &version)?,
+      false => write(Stream::Stdout, &format!("{}\n", version))?,
+    }
+  }
+  exit_with(env, 0)
+}
+
+/// Continuation of the version flag after the async version callback is resolved
+///
+/// Arguments: the version.
+#[js_function(1)]
+fn resume_version(ctx: CallContext) -> Result<JsUnknown> {
+  print_version(*ctx.env, ctx.get::<JsUnknown>(0)?)
+}
+
+/// Parse the raw arguments and run the matched command
+///
+/// The first raw argument is the binary name, which is not passed to clap
+/// when `no_binary_name` is set.
+pub(crate) fn execute(
+  env: Env,
+  clap: &clap::Command,
+  cmd: Command,
+  mut raw_args: Vec<String>,
+  interactive: bool,
+  state: Option<JsObject>,
+) -> Result<JsUnknown> {
+  let bin_name = match clap.is_no_binary_name_set() {
+    true => None,
+    false => resolve_bin_name(&raw_args),
+  };
+  let (mut known_args, unknown_args) = resolve_unknown_args(clap, &cmd, &raw_args);
+  let mut clap = clap.clone();
+  // The binary name is set before the build the same as clap does when parsing,
+  // as the usages of the subcommands are built with it, except for the
+  // multicall commands which are renamed while parsing
+  if !clap.is_multicall_set() {
+    if let Some(bin_name) = bin_name.filter(|_| clap.get_bin_name().is_none()) {
+      clap = clap.bin_name(bin_name);
+    }
+    clap.build();
+  }
+  let clap = &display_negatable_flags(localize_command(clap));
+  if clap.is_no_binary_name_set() && !known_args.is_empty() {
+    known_args.remove(0);
+  }
+  let matches = match clap.clone().try_get_matches_from(&known_args) {
+    Ok(matches) => matches,
+    Err(err) => {
+      if err.kind() == clap::error::ErrorKind::DisplayVersion {
+        if let Some(callback) = find_version_callback(clap, &cmd, &known_args) {
+          let version = callback.call_without_args(None)?;
+          if is_thenable(&version)? {
+            return then(
+              version,
+              env.create_function("resumeVersion", resume_version)?,
+              None,
+            );
+          }
+          return print_version(env, version);
+        }
+      }
+      print_clap_error(clap, &err)?;
+      return exit_with(env, err.exit_code());
+    }
+  };
+
+  let picked = pick_subcommands(&cmd, &matches)?;
+  if !picked.is_empty() {
+    // The picked subcommands follow the arguments of the matched command
+    let index = raw_args
+      .iter()
+      .position(|token| token == "--")
+      .unwrap_or(raw_args.len());
+    raw_args.splice(index..index, picked);
+    let inherited = Inherited {
+      interactive: true,
+      ..Default::default()
+    };
+    let clap = resolve_command(
+      clap::Command::default(),
+      Default::default(),
+      &cmd,
+      &inherited,
+    )?
+    .no_binary_name(clap.is_no_binary_name_set());
+    return execute(env, &clap, cmd, raw_args, true, state);
+  }
 
-  parse_arguments(env, &clap, cmd, &matches, raw_args)
+  parse_arguments(
+    env,
+    clap,
+    cmd,
+    &matches,
+    raw_args,
+    unknown_args,
+    interactive,
+    state,
+  )
 }
@@ -0,0 +1,378 @@
+use napi::{
+  CallContext, Env, JsBoolean, JsFunction, JsNumber, JsObject, JsString, JsUnknown, NapiRaw,
+  NapiValue, ValueType,
+};
+use napi_derive::{js_function, napi};
+
+use crate::testing::{is_capturing, set_result, write, Stream};
+use crate::utils::{COMMAND_ERROR_CODE, COMMAND_EXIT_CODE};
+use crate::HashMap;
+
+/// Command lifecycle hooks
+///
+/// Hooks receive the same `Context` as the command callback and may return
+/// a `Promise`, which is awaited before the next hook is called.
+#[napi(object)]
+pub struct CommandHooks {
+  /// Called before the command callback
+  ///
+  /// The hooks of the matched command and its parent commands are called
+  /// from the root command to the matched subcommand. Throw an error to abort
+  /// the run, or return `false` to skip the remaining hooks and the callback.
+  #[napi(ts_type = "(ctx: Context) => void | boolean | Promise<void | boolean>")]
+  pub pre_run: Option<JsFunction>,
+  /// Called after the command callback succeeds
+  ///
+  /// The hooks are called from the matched subcommand to the root command.
+  #[napi(ts_type = "(ctx: Context) => void | Promise<void>")]
+  pub post_run: Option<JsFunction>,
+  /// Called when a hook or the command callback throws
+  ///
+  /// The hooks are called from the matched subcommand to the root command,
+  /// and the error is rethrown after all the hooks are called.
+  #[napi(ts_type = "(ctx: Context, error: unknown) => void | Promise<void>")]
+  pub on_error: Option<JsFunction>,
+}
+
+/// Get another handle of a js value, which is still owned by the js side
+#[inline]
+pub(crate) fn share<V: NapiRaw>(env: Env, value: &V) -> JsUnknown {
+  unsafe { JsUnknown::from_raw_unchecked(env.raw(), value.raw()) }
+}
+
+fn create_functions(env: Env, functions: Vec<JsFunction>) -> napi::Result<JsObject> {
+  let mut array = env.create_array_with_length(functions.len())?;
+  for (index, function) in functions.into_iter().enumerate() {
+    array.set_element(index as u32, function)?;
+  }
+  Ok(array)
+}
+
+/// Bind the leading arguments of a native continuation
+pub(crate) fn bind(
+  env: Env,
+  function: JsFunction,
+  args: Vec<JsUnknown>,
+) -> napi::Result<JsFunction> {
+  let function = function.coerce_to_object()?;
+  let bind: JsFunction = function.get_named_property("bind")?;
+  let mut bind_args = vec![env.get_undefined()?.into_unknown()];
+  bind_args.extend(args);
+  let bound = bind.call(Some(&function), &bind_args)?;
+  Ok(unsafe { bound.cast() })
+}
+
+pub(crate) fn then(
+  promise: JsUnknown,
+  on_fulfilled: JsFunction,
+  on_rejected: Option<JsFunction>,
+) -> napi::Result<JsUnknown> {
+  let promise = promise.coerce_to_object()?;
+  let then: JsFunction = promise.get_named_property("then")?;
+  let mut args = vec![on_fulfilled.into_unknown()];
+  if let Some(on_rejected) = on_rejected {
+    args.push(on_rejected.into_unknown());
+  }
+  then.call(Some(&promise), &args)
+}
+
+pub(crate) fn is_thenable(value: &JsUnknown) -> napi::Result<bool> {
+  if value.get_type()? != ValueType::Object {
+    return Ok(false);
+  }
+  let then: JsUnknown = unsafe { value.cast::<JsObject>() }.get_named_property("then")?;
+  Ok(then.get_type()? == ValueType::Function)
+}
+
+/// Resolve the state provider of a command into a `preRun` hook
+pub(crate) fn resolve_provider(env: Env, provide: JsFunction) -> napi::Result<CommandHooks> {
+  let pre_run = bind(
+    env,
+    env.create_function("provide", provide_state)?,
+    vec![provide.into_unknown()],
+  )?;
+  Ok(CommandHooks {
+    pre_run: Some(pre_run),
+    post_run: None,
+    on_error: None,
+  })
+}
+
+/// Assign the properties of the provided object to `ctx.state`
+fn assign_state(ctx: &JsObject, provided: JsUnknown) -> napi::Result<()> {
+  if provided.get_type()? != ValueType::Object {
+    return Ok(());
+  }
+  let provided = provided.coerce_to_object()?;
+  let mut state: JsObject = ctx.get_named_property("state")?;
+  let keys = provided.get_property_names()?;
+  for index in 0..keys.get_array_length()? {
+    let key = keys.get_element::<JsString>(index)?.into_utf8()?;
+    let value: JsUnknown = provided.get_named_property(key.as_str()?)?;
+    state.set_named_property(key.as_str()?, value)?;
+  }
+  Ok(())
+}
+
+/// Run the hooks and the callback of the matched command
+///
+/// The steps are stored in a js object so that the continuations of async
+/// hooks can resume the run after the returned `Promise` is settled.
+pub(crate) fn run_with_hooks(
+  env: Env,
+  ctx: JsObject,
+  callback: JsFunction,
+  hooks: Vec<CommandHooks>,
+  exit_codes: HashMap<String, i32>,
+) -> napi::Result<JsUnknown> {
+  let mut pre_run = Vec::new();
+  let mut post_run = Vec::new();
+  let mut on_error = Vec::new();
+  for hooks in hooks {
+    pre_run.extend(hooks.pre_run);
+    post_run.extend(hooks.post_run);
+    on_error.extend(hooks.on_error);
+  }
+  post_run.reverse();
+  on_error.reverse();
+
+  let mut state = env.create_object()?;
+  state.set_named_property("ctx", ctx)?;
+  state.set_named_property("preRunCount", pre_run.len() as u32)?;
+  let steps = pre_run
+    .into_iter()
+    .chain(std::iter::once(callback))
+    .chain(post_run)
+    .collect();
+  state.set_named_property("steps", create_functions(env, steps)?)?;
+  state.set_named_property("onError", create_functions(env, on_error)?)?;
+  state.set_named_property("exitCodes", exit_codes)?;
+  state.set_named_property("exitCode", 0)?;
+
+  resume(env, state, 0)
+}
+
+fn resume(env: Env, mut state: JsObject, start: u32) -> napi::Result<JsUnknown> {
+  let ctx: JsObject = state.get_named_property("ctx")?;
+  let steps: JsObject = state.get_named_property("steps")?;
+  for index in start..steps.get_array_length()? {
+    let step: JsFunction = steps.get_element(index)?;
+    let value = match step.call(None, &[&ctx]) {
+      Ok(value) => value,
+      Err(err) => {
+        let error = napi::JsError::from(err).into_unknown(env);
+        return handle_error(env, state, 0, error);
+      }
+    };
+    if is_thenable(&value)? {
+      let index = env.create_uint32(index)?.into_unknown();
+      let on_fulfilled = bind(
+        env,
+        env.create_function("resume", resume_run)?,
+        vec![share(env, &state), index],
+      )?;
+      let zero = env.create_uint32(0)?.into_unknown();
+      let on_rejected = bind(
+        env,
+        env.create_function("fail", fail_run)?,
+        vec![share(env, &state), zero],
+      )?;
+      return then(value, on_fulfilled, Some(on_rejected));
+    }
+    if settle_step(env, &mut state, index, &value)? {
+      break;
+    }
+  }
+  let exit_code: i32 = state.get_named_property("exitCode")?;
+  exit_with(env, exit_code)
+}
+
+/// Settle the returned value of a step
+///
+/// The number returned by the callback is used as the exit code, and
+/// returns `true` if the run is aborted by a `preRun` hook returning `false`.
+fn settle_step(
+  env: Env,
+  state: &mut JsObject,
+  index: u32,
+  value: &JsUnknown,
+) -> napi::Result<bool> {
+  let pre_run_count: u32 = state.get_named_property("preRunCount")?;
+  if index == pre_run_count {
+    set_result(env, value)?;
+  }
+  match value.get_type()? {
+    ValueType::Boolean if index < pre_run_count => {
+      Ok(!unsafe { value.cast::<JsBoolean>() }.get_value()?)
+    }
+    ValueType::Number if index == pre_run_count => {
+      let exit_code = unsafe { value.cast::<JsNumber>() }.get_int32()?;
+      state.set_named_property("exitCode", exit_code)?;
+      Ok(false)
+    }
+    _ => Ok(false),
+  }
+}
+
+/// End the run with the exit code
+///
+/// The exit code is set to `process.exitCode` instead of exiting the process,
+/// so that the pending tasks and output streams are not interrupted.
+pub(crate) fn exit_with(env: Env, exit_code: i32) -> napi::Result<JsUnknown> {
+  if exit_code != 0 && !is_capturing() {
+    let mut process: JsObject = env.get_global()?.get_named_property("process")?;
+    process.set_named_property("exitCode", exit_code)?;
+  }
+  Ok(env.create_int32(exit_code)?.into_unknown())
+}
+
+fn handle_error(
+  env: Env,
+  state: JsObject,
+  start: u32,
+  error: JsUnknown,
+) -> napi::Result<JsUnknown> {
+  if let Some(exit_code) = resolve_error_code(&error, COMMAND_EXIT_CODE)? {
+    return exit_with(env, exit_code);
+  }
+  let ctx: JsObject = state.get_named_property("ctx")?;
+  let on_error: JsObject = state.get_named_property("onError")?;
+  for index in start..on_error.get_array_length()? {
+    let hook: JsFunction = on_error.get_element(index)?;
+    let value = hook.call(None, &[share(env, &ctx), share(env, &error)])?;
+    if is_thenable(&value)? {
+      let next = env.create_uint32(index + 1)?.into_unknown();
+      let on_fulfilled = bind(
+        env,
+        env.create_function("fail", fail_run)?,
+        vec![share(env, &state), next, error],
+      )?;
+      return then(value, on_fulfilled, None);
+    }
+  }
+  if let Some(exit_code) = resolve_error_code(&error, COMMAND_ERROR_CODE)? {
+    let output: String = unsafe { error.cast::<JsObject>() }.get_named_property("output")?;
+    write(Stream::Stderr, &output)?;
+    return exit_with(env, exit_code);
+  }
+  let exit_codes: JsObject = state.get_named_property("exitCodes")?;
+  if let Some(exit_code) = resolve_mapped_exit_code(&error, &exit_codes)? {
+    let message: JsUnknown = unsafe { error.cast::<JsObject>() }.get_named_property("message")?;
+    let message = message.coerce_to_string()?.into_utf8()?;
+    write(Stream::Stderr, &format!("error: {}\n", message.as_str()?))?;
+    return exit_with(env, exit_code);
+  }
+  env.throw(error)?;
+  Err(napi::Error::new(napi::Status::PendingException, ""))
+}
+
+fn get_string(object: &JsObject, key: &str) -> napi::Result<Option<String>> {
+  let value: JsUnknown = object.get_named_property(key)?;
+  if value.get_type()? != ValueType::String {
+    return Ok(None);
+  }
+  Ok(Some(
+    unsafe { value.cast::<JsString>() }
+      .into_utf8()?
+      .into_owned()?,
+  ))
+}
+
+/// Get the exit code if the error is raised by the context with the given code,
+/// e.g. `ctx.error`, `ctx.fail` or `ctx.exit`
+fn resolve_error_code(error: &JsUnknown, code: &str) -> napi::Result<Option<i32>> {
+  if error.get_type()? != ValueType::Object {
+    return Ok(None);
+  }
+  let error = unsafe { error.cast::<JsObject>() };
+  if get_string(&error, "code")?.as_deref() != Some(code) {
+    return Ok(None);
+  }
+  Ok(Some(error.get_named_property("exitCode")?))
+}
+
+/// Get the exit code of the error from the `exitCodes` of the command
+///
+/// The error is matched by its `code` first, and then by the names of its
+/// class and the parent classes, e.g. `ENOENT`, `NotFoundError` or `Error`.
+fn resolve_mapped_exit_code(error: &JsUnknown, exit_codes: &JsObject) -> napi::Result<Option<i32>> {
+  if error.get_type()? != ValueType::Object {
+    return Ok(None);
+  }
+  let error = unsafe { error.cast::<JsObject>() };
+  let mut keys = Vec::from_iter(get_string(&error, "code")?);
+  let mut prototype: JsUnknown = error.get_prototype()?;
+  while prototype.get_type()? == ValueType::Object {
+    let object = unsafe { prototype.cast::<JsObject>() };
+    let constructor: JsUnknown = object.get_named_property("constructor")?;
+    if constructor.get_type()? == ValueType::Function {
+      keys.extend(get_string(
+        &unsafe { constructor.cast::<JsObject>() },
+        "name",
+      )?);
+    }
+    prototype = object.get_prototype()?;
+  }
+  for key in keys {
+    if exit_codes.has_own_property(&key)? {
+      return Ok(Some(exit_codes.get_named_property(&key)?));
+    }
+  }
+  Ok(None)
+}
+
+/// Continuation of the run after an async step is fulfilled
+///
+/// Arguments: `state`, `index` of the settled step and the resolved value.
+#[js_function(3)]
+fn resume_run(ctx: CallContext) -> napi::Result<JsUnknown> {
+  let mut state = ctx.get::<JsObject>(0)?;
+  let index = ctx.get::<JsNumber>(1)?.get_uint32()?;
+  let value = ctx.get::<JsUnknown>(2)?;
+  if settle_step(*ctx.env, &mut state, index, &value)? {
+    let exit_code: i32 = state.get_named_property("exitCode")?;
+    return exit_with(*ctx.env, exit_code);
+  }
+  resume(*ctx.env, state, index + 1)
+}
+
+/// Continuation of the run after an async step is rejected
+///
+/// Arguments: `state`, `index` of the next error hook and the error.
+#[js_function(3)]
+fn fail_run(ctx: CallContext) -> napi::Result<JsUnknown> {
+  let state = ctx.get::<JsObject>(0)?;
+  let index = ctx.get::<JsNumber>(1)?.get_uint32()?;
+  let error = ctx.get::<JsUnknown>(2)?;
+  handle_error(*ctx.env, state, index, error)
+}
+
+/// `preRun` hook of a state provider
+///
+/// Arguments: the `provide` function of the command and the context.
+#[js_function(2)]
+fn provide_state(ctx: CallContext) -> napi::Result<JsUnknown> {
+  let provide = ctx.get::<JsFunction>(0)?;
+  let context = ctx.get::<JsObject>(1)?;
+  let provided = provide.call(None, &[&context])?;
+  if is_thenable(&provided)? {
+    let on_fulfilled = bind(
+      *ctx.env,
+      ctx.env.create_function("assign", assign_provided)?,
+      vec![context.into_unknown()],
+    )?;
+    return then(provided, on_fulfilled, None);
+  }
+  assign_state(&context, provided)?;
+  Ok(ctx.env.get_undefined()?.into_unknown())
+}
+
+/// Continuation of an async state provider
+///
+/// Arguments: the context and the resolved value of the provider.
+#[js_function(2)]
+fn assign_provided(ctx: CallContext) -> napi::Result<JsUnknown> {
+  let context = ctx.get::<JsObject>(0)?;
+  assign_state(&context, ctx.get::<JsUnknown>(1)?)?;
+  Ok(ctx.env.get_undefined()?.into_unknown())
+}
@@ -1,13 +1,18 @@
 use std::rc::Rc;
 
+use clap::builder::styling::{AnsiColor, Color, Effects, Style};
 use inquire::validator::{ErrorMessage, StringValidator, Validation};
-use napi::JsFunction;
+use napi::{Either, Env, JsFunction, JsUnknown};
 use napi_derive::napi;
 
 use crate::{
   apply_opt,
-  types::{Context, Error},
-  utils::{as_usize, leak_str, wrap_bool_formatter, wrap_bool_parser, wrap_string_formatter},
+  locale::{format_message, localized, message},
+  testing::{answer_bool, answer_choices, answer_text, is_colored, write, Stream},
+  types::{CommandMeta, CommandOption, Context, Error},
+  utils::{
+    as_usize, leak_str, split_words, wrap_bool_formatter, wrap_bool_parser, wrap_string_formatter,
+  },
 };
 
 #[napi(object)]
@@ -29,8 +34,14 @@ pub fn select(
   choices: Vec<String>,
   config: Option<SelectConfig>,
 ) -> napi::Result<String> {
+  if let Some(answer) = answer_choice(&prompt, &choices)? {
+    return Ok(answer);
+  }
   let mut inquire = inquire::Select::new(&prompt, choices);
   let config = config.unwrap_or_default();
+  if let Some(help_message) = localized("select-help") {
+    inquire = inquire.with_help_message(help_message);
+  }
 
   apply_opt!(inquire, config, leak_str(help_message) => with_help_message);
   apply_opt!(inquire, config, as_usize(page_size) => with_page_size);
@@ -49,6 +60,35 @@ pub fn select(
   Ok(inquire.prompt().map_err(Error::InquireError)?)
 }
 
+/// Select one of the values displayed by the labels
+///
+/// The answers of the tests are the values instead of the labels.
+pub(crate) fn select_labeled(
+  prompt: String,
+  values: &[String],
+  labels: Vec<String>,
+) -> napi::Result<String> {
+  if let Some(answer) = answer_choice(&prompt, values)? {
+    return Ok(answer);
+  }
+  let label = select(prompt, labels.clone(), None)?;
+  let index = labels.iter().position(|item| *item == label).unwrap();
+  Ok(values[index].clone())
+}
+
+fn answer_choice(prompt: &str, choices: &[String]) -> napi::Result<Option<String>> {
+  let Some(mut answer) = answer_choices(prompt, choices)? else {
+    return Ok(None);
+  };
+  if answer.len() != 1 {
+    return Err(napi::Error::from_reason(format!(
+      "Expected exactly one choice for the prompt `{}`",
+      prompt
+    )));
+  }
+  Ok(Some(answer.remove(0)))
+}
+
 #[napi(object)]
 #[derive(Default)]
 pub struct CheckboxConfig {
@@ -71,8 +111,14 @@ pub fn checkbox(
   choices: Vec<String>,
   config: Option<CheckboxConfig>,
 ) -> napi::Result<Vec<String>> {
+  if let Some(answer) = answer_choices(&prompt, &choices)? {
+    return Ok(answer);
+  }
   let mut inquire = inquire::MultiSelect::new(&prompt, choices);
   let config = config.unwrap_or_default();
+  if let Some(help_message) = localized("checkbox-help") {
+    inquire = inquire.with_help_message(help_message);
+  }
   if let Some(true) = config.all_selected_by_default {
     inquire = inquire.with_all_selected_by_default();
   }
@@ -129,6 +175,9 @@ pub struct InputConfig {
 
 #[napi]
 pub fn input(prompt: String, config: Option<InputConfig>) -> napi::Result<String> {
+  if let Some(answer) = answer_text(&prompt, false)? {
+    return Ok(answer);
+  }
   let mut inquire = inquire::Text::new(&prompt);
   let config = config.unwrap_or_default();
   apply_opt!(inquire, config, leak_str(default) => with_default);
@@ -165,8 +214,14 @@ pub struct ConfirmConfig {
 
 #[napi]
 pub fn confirm(prompt: String, config: Option<ConfirmConfig>) -> napi::Result<bool> {
+  if let Some(answer) = answer_bool(&prompt)? {
+    return Ok(answer);
+  }
   let mut inquire = inquire::Confirm::new(&prompt);
   let config = config.unwrap_or_default();
+  if let Some(error_message) = localized("confirm-error") {
+    inquire = inquire.with_error_message(error_message);
+  }
   apply_opt!(inquire, config, default => with_default);
   apply_opt!(inquire, config, wrap_bool_formatter(default_value_formatter) => with_default_value_formatter);
   apply_opt!(inquire, config, leak_str(error_message) => with_error_message);
@@ -216,8 +271,17 @@ fn validator(f: Rc<JsFunction>) -> Box<dyn StringValidator> {
 
 #[napi]
 pub fn password(prompt: String, config: Option<PasswordConfig>) -> napi::Result<String> {
+  if let Some(answer) = answer_text(&prompt, true)? {
+    return Ok(answer);
+  }
   let mut inquire = inquire::Password::new(&prompt);
   let config = config.unwrap_or_default();
+  if let Some(message) = localized("password-confirmation") {
+    inquire = inquire.with_custom_confirmation_message(message);
+  }
+  if let Some(message) = localized("password-mismatch") {
+    inquire = inquire.with_custom_confirmation_error_message(message);
+  }
   apply_opt!(inquire, config, leak_str(custom_confirmation_error_message) => with_custom_confirmation_error_message);
   apply_opt!(inquire, config, leak_str(custom_confirmation_message) => with_custom_confirmation_message);
   if let Some(display_mode) = config.display_mode {
@@ -253,6 +317,228 @@ pub fn password(prompt: String, config: Option<PasswordConfig>) -> napi::Result<
   Ok(inquire.prompt().map_err(Error::InquireError)?)
 }
 
+/// Whether the option is prompted when it is missing in the interactive mode
+pub(crate) fn is_promptable(opt: &CommandOption) -> bool {
+  if matches!(opt.action.as_deref(), Some("count" | "map")) {
+    return false;
+  }
+  match &opt.prompt {
+    Some(Either::B(prompt)) => *prompt,
+    Some(Either::A(_)) => true,
+    None => opt.required.unwrap_or(false),
+  }
+}
+
+/// Apply the theme of the command to the prompts
+///
+/// The prompts keep their own colors if the command has no theme, and are not
+/// colored if the colors are disabled.
+pub(crate) fn apply_prompt_theme(meta: &CommandMeta, clap: &clap::Command) {
+  use inquire::ui::{ErrorMessageRenderConfig, RenderConfig, Styled};
+
+  let config = match (is_colored(clap, Stream::Stderr), meta.is_themed()) {
+    (false, _) => RenderConfig::empty(),
+    (true, false) => RenderConfig::default_colored(),
+    (true, true) => {
+      let styles = clap.get_styles();
+      let header = resolve_style_sheet(styles.get_header());
+      let literal = resolve_style_sheet(styles.get_literal());
+      let placeholder = resolve_style_sheet(styles.get_placeholder());
+      let error = resolve_style_sheet(styles.get_error());
+      let valid = resolve_style_sheet(styles.get_valid());
+      let invalid = resolve_style_sheet(styles.get_invalid());
+      RenderConfig {
+        prompt_prefix: Styled::new("?").with_style_sheet(header),
+        answered_prompt_prefix: Styled::new(">").with_style_sheet(valid),
+        placeholder,
+        default_value: placeholder,
+        help_message: placeholder,
+        answer: valid,
+        error_message: ErrorMessageRenderConfig::empty()
+          .with_prefix(Styled::new("#").with_style_sheet(error))
+          .with_message(error),
+        canceled_prompt_indicator: Styled::new("<canceled>").with_style_sheet(invalid),
+        highlighted_option_prefix: Styled::new(">").with_style_sheet(literal),
+        selected_checkbox: Styled::new("[x]").with_style_sheet(literal),
+        selected_option: Some(literal),
+        ..RenderConfig::empty()
+      }
+    }
+  };
+  inquire::set_global_render_config(config);
+}
+
+/// Restore the default theme of the prompts after the run
+pub(crate) fn reset_prompt_theme() {
+  inquire::set_global_render_config(inquire::ui::RenderConfig::default());
+}
+
+fn resolve_style_sheet(style: &Style) -> inquire::ui::StyleSheet {
+  use inquire::ui::{Attributes, StyleSheet};
+
+  let mut sheet = StyleSheet::empty();
+  if let Some(color) = style.get_fg_color() {
+    sheet = sheet.with_fg(resolve_prompt_color(color));
+  }
+  if let Some(color) = style.get_bg_color() {
+    sheet = sheet.with_bg(resolve_prompt_color(color));
+  }
+  // Only bold and italic are supported by the prompts
+  let effects = style.get_effects();
+  let mut attributes = Attributes::empty();
+  if effects.contains(Effects::BOLD) {
+    attributes |= Attributes::BOLD;
+  }
+  if effects.contains(Effects::ITALIC) {
+    attributes |= Attributes::ITALIC;
+  }
+  sheet.with_attr(attributes)
+}
+
+fn resolve_prompt_color(color: Color) -> inquire::ui::Color {
+  use inquire::ui::Color as PromptColor;
+
+  match color {
+    Color::Ansi(color) => match color {
+      AnsiColor::Black => PromptColor::Black,
+      AnsiColor::Red => PromptColor::DarkRed,
+      AnsiColor::Green => PromptColor::DarkGreen,
+      AnsiColor::Yellow => PromptColor::DarkYellow,
+      AnsiColor::Blue => PromptColor::DarkBlue,
+      AnsiColor::Magenta => PromptColor::DarkMagenta,
+      AnsiColor::Cyan => PromptColor::DarkCyan,
+      AnsiColor::White => PromptColor::Grey,
+      AnsiColor::BrightBlack => PromptColor::DarkGrey,
+      AnsiColor::BrightRed => PromptColor::LightRed,
+      AnsiColor::BrightGreen => PromptColor::LightGreen,
+      AnsiColor::BrightYellow => PromptColor::LightYellow,
+      AnsiColor::BrightBlue => PromptColor::LightBlue,
+      AnsiColor::BrightMagenta => PromptColor::LightMagenta,
+      AnsiColor::BrightCyan => PromptColor::LightCyan,
+      AnsiColor::BrightWhite => PromptColor::White,
+    },
+    Color::Ansi256(color) => PromptColor::AnsiValue(color.0),
+    Color::Rgb(color) => PromptColor::Rgb {
+      r: color.0,
+      g: color.1,
+      b: color.2,
+    },
+  }
+}
+
+fn parse_answer(env: Env, parser: &str, answer: &str) -> napi::Result<Option<JsUnknown>> {
+  Ok(match parser {
+    "number" => match answer.trim().parse::<i64>() {
+      Ok(value) => Some(env.create_int64(value)?.into_unknown()),
+      Err(_) => None,
+    },
+    "boolean" => match answer.trim().parse::<bool>() {
+      Ok(value) => Some(env.get_boolean(value)?.into_unknown()),
+      Err(_) => None,
+    },
+    _ => Some(env.create_string(answer)?.into_unknown()),
+  })
+}
+
+fn create_array(env: Env, values: Vec<JsUnknown>) -> napi::Result<JsUnknown> {
+  let mut array = env.create_array_with_length(values.len())?;
+  for (index, value) in values.into_iter().enumerate() {
+    array.set_element(index as u32, value)?;
+  }
+  Ok(array.into_unknown())
+}
+
+/// Prompt for the value of a missing option, the invalid answers are asked again
+pub(crate) fn prompt_option(
+  env: Env,
+  name: &str,
+  opt: &CommandOption,
+  flag: bool,
+  multiple: bool,
+) -> napi::Result<JsUnknown> {
+  let message = match &opt.prompt {
+    Some(Either::A(message)) => message.clone(),
+    _ => opt.help.unwrap_or(name).to_string(),
+  };
+  let parser = opt.parser.as_deref().unwrap_or("string");
+
+  if flag || (parser == "boolean" && !multiple) {
+    let config = ConfirmConfig {
+      default: opt.default.and_then(|default| default.parse().ok()),
+      ..Default::default()
+    };
+    return Ok(
+      env
+        .get_boolean(confirm(message, Some(config))?)?
+        .into_unknown(),
+    );
+  }
+  if let Some(choices) = &opt.choices {
+    let choices = choices.iter().map(|choice| choice.to_string()).collect();
+    return match multiple {
+      true => {
+        let values = checkbox(message, choices, None)?
+          .into_iter()
+          .map(|value| Ok(env.create_string(&value)?.into_unknown()))
+          .collect::<napi::Result<Vec<JsUnknown>>>()?;
+        create_array(env, values)
+      }
+      false => Ok(
+        env
+          .create_string(&select(message, choices, None)?)?
+          .into_unknown(),
+      ),
+    };
+  }
+
+  loop {
+    let answer = match opt.sensitive.unwrap_or(false) {
+      true => password(
+        message.clone(),
+        Some(PasswordConfig {
+          confirmation: Some(false),
+          ..Default::default()
+        }),
+      )?,
+      false => input(
+        message.clone(),
+        Some(InputConfig {
+          default: opt.default.map(|default| default.to_string()),
+          ..Default::default()
+        }),
+      )?,
+    };
+    let answers = match multiple {
+      true => split_words(&answer)?,
+      false => vec![answer],
+    };
+    let mut values = Vec::with_capacity(answers.len());
+    for answer in &answers {
+      match parse_answer(env, parser, answer)? {
+        Some(value) => values.push(value),
+        None => {
+          write(
+            Stream::Stderr,
+            &format!(
+              "{} {}\n",
+              self::message("error"),
+              format_message("invalid-answer", &[("parser", parser), ("value", answer)])
+            ),
+          )?;
+          break;
+        }
+      }
+    }
+    if values.len() < answers.len() {
+      continue;
+    }
+    return match multiple {
+      true => create_array(env, values),
+      false => Ok(values.remove(0)),
+    };
+  }
+}
+
 #[napi]
 impl Context {
   #[napi]
@@ -1,7 +1,11 @@
 pub mod command;
+pub mod hooks;
 pub mod inquire;
+pub mod locale;
 pub mod progressbar;
+pub mod repl;
 pub mod resolver;
+pub mod testing;
 pub mod types;
 pub mod utils;
 
@@ -0,0 +1,753 @@
+use std::cell::RefCell;
+use std::fmt::Write;
+
+use clap::builder::styling::Styles;
+use clap::builder::StyledStr;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use napi_derive::napi;
+
+use crate::types::CommandMeta;
+use crate::utils::leak_str;
+use crate::HashMap;
+
+/// Built-in messages in English, the same as clap and inquire
+const EN: &[(&str, &str)] = &[
+  ("usage-heading", "Usage"),
+  ("commands-heading", "Commands"),
+  ("arguments-heading", "Arguments"),
+  ("options-heading", "Options"),
+  ("examples-heading", "Examples"),
+  ("help-flag", "Print help"),
+  ("help-flag-short", "Print help (see more with '--help')"),
+  ("help-flag-long", "Print help (see a summary with '-h')"),
+  ("version-flag", "Print version"),
+  (
+    "help-subcommand",
+    "Print this message or the help of the given subcommand(s)",
+  ),
+  ("default-value", "default"),
+  ("possible-values", "possible values"),
+  ("env-value", "env"),
+  ("aliases", "aliases"),
+  ("subcommands", "subcommands"),
+  ("error", "error:"),
+  ("tip", "tip:"),
+  ("more-information", "For more information, try '{help}'."),
+  ("unexpected-argument", "unexpected argument '{arg}' found"),
+  ("invalid-value", "invalid value '{value}' for '{arg}'"),
+  (
+    "value-required",
+    "a value is required for '{arg}' but none was supplied",
+  ),
+  (
+    "invalid-subcommand",
+    "unrecognized subcommand '{subcommand}'",
+  ),
+  (
+    "missing-required-arguments",
+    "the following required arguments were not provided:",
+  ),
+  (
+    "missing-subcommand",
+    "'{subcommand}' requires a subcommand but one was not provided",
+  ),
+  (
+    "argument-conflict",
+    "the argument '{arg}' cannot be used with '{other}'",
+  ),
+  (
+    "argument-conflicts",
+    "the argument '{arg}' cannot be used with:",
+  ),
+  (
+    "argument-conflict-others",
+    "the argument '{arg}' cannot be used with one or more of the other specified arguments",
+  ),
+  (
+    "argument-repeated",
+    "the argument '{arg}' cannot be used multiple times",
+  ),
+  (
+    "no-equals",
+    "equal sign is needed when assigning values to '{arg}'",
+  ),
+  (
+    "too-many-values",
+    "unexpected value '{value}' for '{arg}' found; no more were expected",
+  ),
+  (
+    "too-few-values",
+    "{expected} values required by '{arg}'; only {actual} were provided",
+  ),
+  (
+    "too-few-values-one",
+    "{expected} values required by '{arg}'; only {actual} was provided",
+  ),
+  (
+    "wrong-number-of-values",
+    "{expected} values required for '{arg}' but {actual} were provided",
+  ),
+  (
+    "wrong-number-of-values-one",
+    "{expected} values required for '{arg}' but {actual} was provided",
+  ),
+  (
+    "similar-subcommand",
+    "a similar subcommand exists: {values}",
+  ),
+  (
+    "similar-subcommands",
+    "some similar subcommands exist: {values}",
+  ),
+  ("similar-argument", "a similar argument exists: {values}"),
+  (
+    "similar-arguments",
+    "some similar arguments exist: {values}",
+  ),
+  ("similar-value", "a similar value exists: {values}"),
+  ("similar-values", "some similar values exist: {values}"),
+  ("unknown-command", "unknown command `{name}`"),
+  ("invalid-answer", "invalid {parser} `{value}`"),
+  ("select-command", "Select a command"),
+  ("select-help", "↑↓ to move, enter to select, type to filter"),
+  (
+    "checkbox-help",
+    "↑↓ to move, space to select one, → to all, ← to none, type to filter",
+  ),
+  (
+    "confirm-error",
+    "Invalid answer, try typing 'y' for yes or 'n' for no",
+  ),
+  ("password-confirmation", "Confirmation:"),
+  ("password-mismatch", "The answers don't match."),
+];
+
+/// Built-in messages in Simplified Chinese
+const ZH_CN: &[(&str, &str)] = &[
+  ("usage-heading", "用法"),
+  ("commands-heading", "命令"),
+  ("arguments-heading", "参数"),
+  ("options-heading", "选项"),
+  ("examples-heading", "示例"),
+  ("help-flag", "打印帮助"),
+  ("help-flag-short", "打印帮助（使用 '--help' 查看更多）"),
+  ("help-flag-long", "打印帮助（使用 '-h' 查看摘要）"),
+  ("version-flag", "打印版本"),
+  ("help-subcommand", "打印此帮助或给定子命令的帮助"),
+  ("default-value", "默认值"),
+  ("possible-values", "可选值"),
+  ("env-value", "环境变量"),
+  ("aliases", "别名"),
+  ("subcommands", "子命令"),
+  ("error", "错误:"),
+  ("tip", "提示:"),
+  ("more-information", "更多信息请尝试 '{help}'。"),
+  ("unexpected-argument", "发现未知参数 '{arg}'"),
+  ("invalid-value", "参数 '{arg}' 的值 '{value}' 无效"),
+  ("value-required", "参数 '{arg}' 需要一个值，但未提供"),
+  ("invalid-subcommand", "无法识别的子命令 '{subcommand}'"),
+  ("missing-required-arguments", "缺少以下必需参数:"),
+  (
+    "missing-subcommand",
+    "'{subcommand}' 需要一个子命令，但未提供",
+  ),
+  (
+    "argument-conflict",
+    "参数 '{arg}' 不能与 '{other}' 一起使用",
+  ),
+  ("argument-conflicts", "参数 '{arg}' 不能与以下参数一起使用:"),
+  (
+    "argument-conflict-others",
+    "参数 '{arg}' 不能与指定的其他参数一起使用",
+  ),
+  ("argument-repeated", "参数 '{arg}' 不能多次使用"),
+  ("no-equals", "为 '{arg}' 赋值时需要使用等号"),
+  ("too-many-values", "发现参数 '{arg}' 的多余值 '{value}'"),
+  (
+    "too-few-values",
+    "参数 '{arg}' 需要 {expected} 个值，但只提供了 {actual} 个",
+  ),
+  (
+    "too-few-values-one",
+    "参数 '{arg}' 需要 {expected} 个值，但只提供了 {actual} 个",
+  ),
+  (
+    "wrong-number-of-values",
+    "参数 '{arg}' 需要 {expected} 个值，但提供了 {actual} 个",
+  ),
+  (
+    "wrong-number-of-values-one",
+    "参数 '{arg}' 需要 {expected} 个值，但提供了 {actual} 个",
+  ),
+  ("similar-subcommand", "存在相似的子命令: {values}"),
+  ("similar-subcommands", "存在相似的子命令: {values}"),
+  ("similar-argument", "存在相似的参数: {values}"),
+  ("similar-arguments", "存在相似的参数: {values}"),
+  ("similar-value", "存在相似的值: {values}"),
+  ("similar-values", "存在相似的值: {values}"),
+  ("unknown-command", "未知命令 `{name}`"),
+  ("invalid-answer", "无效的 {parser} `{value}`"),
+  ("select-command", "选择命令"),
+  ("select-help", "↑↓ 移动，回车选择，输入以筛选"),
+  (
+    "checkbox-help",
+    "↑↓ 移动，空格选择，→ 全选，← 全不选，输入以筛选",
+  ),
+  (
+    "confirm-error",
+    "无效的回答，请输入 'y' 表示是或 'n' 表示否",
+  ),
+  ("password-confirmation", "确认:"),
+  ("password-mismatch", "两次输入不一致。"),
+];
+
+const TAB: &str = "  ";
+
+struct Messages {
+  catalog: &'static [(&'static str, &'static str)],
+  overrides: HashMap<String, String>,
+}
+
+thread_local! {
+  /// Messages of the running command, or `None` if the output of clap and
+  /// inquire is used as is
+  static MESSAGES: RefCell<Option<Messages>> = const { RefCell::new(None) };
+}
+
+fn resolve_catalog(locale: &str) -> napi::Result<&'static [(&'static str, &'static str)]> {
+  match locale {
+    "en" => Ok(EN),
+    "zh-CN" => Ok(ZH_CN),
+    _ => Err(napi::Error::new(
+      napi::Status::InvalidArg,
+      format!("Unsupported locale `{}`, expected `en` or `zh-CN`", locale),
+    )),
+  }
+}
+
+fn lookup(catalog: &[(&str, &'static str)], id: &str) -> Option<&'static str> {
+  catalog
+    .iter()
+    .find(|(key, _)| *key == id)
+    .map(|(_, message)| *message)
+}
+
+/// Use the locale and the messages of the main command for the run
+pub(crate) fn set_locale(meta: &CommandMeta) -> napi::Result<()> {
+  let messages = match (&meta.locale, &meta.messages) {
+    (None, None) => None,
+    (locale, overrides) => Some(Messages {
+      catalog: resolve_catalog(locale.as_deref().unwrap_or("en"))?,
+      overrides: overrides.clone().unwrap_or_default(),
+    }),
+  };
+  MESSAGES.set(messages);
+  Ok(())
+}
+
+/// Restore the output of clap and inquire after the run
+pub(crate) fn reset_locale() {
+  MESSAGES.set(None);
+}
+
+/// Whether the built-in messages are localized
+pub(crate) fn is_localized() -> bool {
+  MESSAGES.with_borrow(|messages| messages.is_some())
+}
+
+/// Get the message of the running command
+pub(crate) fn message(id: &str) -> String {
+  let message = MESSAGES.with_borrow(|messages| {
+    let messages = messages.as_ref()?;
+    messages
+      .overrides
+      .get(id)
+      .cloned()
+      .or_else(|| lookup(messages.catalog, id).map(str::to_string))
+  });
+  message.unwrap_or_else(|| {
+    lookup(EN, id)
+      .unwrap_or_else(|| panic!("Unknown message: {:?}", id))
+      .to_string()
+  })
+}
+
+/// Get the message of the running command if the messages are localized,
+/// to replace the defaults of the prompts
+pub(crate) fn localized(id: &str) -> Option<&'static str> {
+  is_localized().then(|| leak_str(message(id)))
+}
+
+/// Get the message with the placeholders, e.g. `{arg}`, replaced by the values
+pub(crate) fn format_message(id: &str, values: &[(&str, &str)]) -> String {
+  values.iter().fold(message(id), |message, (name, value)| {
+    message.replace(&format!("{{{}}}", name), value)
+  })
+}
+
+/// Get the built-in messages of the locale
+///
+/// The messages are keyed by their ids, which can be overridden by the
+/// `messages` of the command metadata.
+///
+/// @param locale Locale of the messages, defaults to `en`
+/// @returns {Record<string, string>}
+#[napi(ts_args_type = "locale?: 'en' | 'zh-CN'")]
+pub fn get_messages(locale: Option<String>) -> napi::Result<HashMap<String, String>> {
+  let catalog = resolve_catalog(locale.as_deref().unwrap_or("en"))?;
+  Ok(
+    catalog
+      .iter()
+      .map(|(id, message)| (id.to_string(), message.to_string()))
+      .collect(),
+  )
+}
+
+/// Help template of clap with the localized usage heading
+pub(crate) fn help_template(styles: &Styles) -> StyledStr {
+  let usage = styles.get_usage();
+  StyledStr::from(format!(
+    "{{before-help}}{{about-with-newline}}\n{usage}{}:{usage:#} {{usage}}\n\n{{all-args}}{{after-help}}",
+    message("usage-heading")
+  ))
+}
+
+/// Replace the usage heading rendered by clap, e.g. of `ctx.usage()`
+fn localize_usage_heading(usage: &str, styles: &Styles) -> String {
+  let style = styles.get_usage();
+  match usage.strip_prefix(&format!("{style}Usage:{style:#} ")) {
+    Some(usage) => format!("{style}{}:{style:#} {}", message("usage-heading"), usage),
+    None => usage.to_string(),
+  }
+}
+
+/// Localize the usage rendered by clap
+pub(crate) fn localize_usage(usage: StyledStr, styles: &Styles) -> StyledStr {
+  match is_localized() {
+    true => StyledStr::from(localize_usage_heading(&usage.ansi().to_string(), styles)),
+    false => usage,
+  }
+}
+
+/// Append the details of the argument, which clap appends to the help in
+/// English, e.g. the default value and the possible values, to its help
+///
+/// The details are rendered the same as clap does, while clap is told to
+/// hide them. The visible aliases are given, as they are added as hidden
+/// aliases instead.
+pub(crate) fn localize_arg(mut arg: clap::Arg, aliases: Vec<String>, long_help: bool) -> clap::Arg {
+  let takes_values = arg
+    .get_num_args()
+    .map_or_else(|| arg.get_action().takes_values(), |num| num.takes_values());
+  let mut details = Vec::new();
+  if let Some(name) = arg.get_env().filter(|_| !arg.is_hide_env_set()) {
+    let value = std::env::var_os(name).unwrap_or_default();
+    details.push(format!(
+      "[{}: {}={}]",
+      message("env-value"),
+      name.to_string_lossy(),
+      value.to_string_lossy()
+    ));
+  }
+  if takes_values && !arg.is_hide_default_value_set() && !arg.get_default_values().is_empty() {
+    let values = arg
+      .get_default_values()
+      .iter()
+      .map(|value| escape(&value.to_string_lossy()))
+      .collect::<Vec<_>>();
+    details.push(format!(
+      "[{}: {}]",
+      message("default-value"),
+      values.join(" ")
+    ));
+  }
+  if !aliases.is_empty() {
+    details.push(format!("[{}: {}]", message("aliases"), aliases.join(", ")));
+  }
+  if takes_values && !arg.is_hide_possible_values_set() {
+    let values = arg
+      .get_value_parser()
+      .possible_values()
+      .into_iter()
+      .flatten()
+      .filter(|value| !value.is_hide_set())
+      .map(|value| escape(value.get_name()))
+      .collect::<Vec<_>>();
+    if !values.is_empty() {
+      details.push(format!(
+        "[{}: {}]",
+        message("possible-values"),
+        values.join(", ")
+      ));
+    }
+  }
+  if details.is_empty() {
+    return arg;
+  }
+
+  // Joined the same as clap does for the short and the long help
+  let append = |help: Option<&StyledStr>, separator: &str, connector: &str| {
+    let mut help = help.map(|help| help.ansi().to_string()).unwrap_or_default();
+    if !help.is_empty() {
+      help.push_str(separator);
+    }
+    help.push_str(&details.join(connector));
+    StyledStr::from(help)
+  };
+  let help = append(arg.get_help(), " ", " ");
+  if long_help {
+    let long = append(arg.get_long_help().or(arg.get_help()), "\n\n", "\n");
+    arg = arg.long_help(long);
+  }
+  arg = arg.help(help).hide_env(true);
+  if takes_values {
+    arg = arg.hide_default_value(true).hide_possible_values(true);
+  }
+  arg
+}
+
+/// The built-in help flag with the localized help
+pub(crate) fn help_flag(long_help: bool) -> clap::Arg {
+  let arg = clap::Arg::new("help")
+    .short('h')
+    .long("help")
+    .action(clap::ArgAction::Help);
+  match long_help {
+    true => arg
+      .help(message("help-flag-short"))
+      .long_help(message("help-flag-long")),
+    false => arg.help(message("help-flag")),
+  }
+}
+
+/// Localize the built-in `help` subcommands of the built command, which are
+/// only generated by clap when the command is built
+///
+/// The multicall commands are renamed when parsing, so they are not built early.
+pub(crate) fn localize_command(clap: clap::Command) -> clap::Command {
+  if !is_localized() || clap.is_multicall_set() {
+    return clap;
+  }
+  localize_help_subcommand(clap)
+}
+
+fn localize_help_subcommand(mut clap: clap::Command) -> clap::Command {
+  let names = clap
+    .get_subcommands()
+    .map(|sub_command| sub_command.get_name().to_string())
+    .filter(|name| name != "help")
+    .collect::<Vec<_>>();
+  for name in names {
+    clap = clap.mut_subcommand(name, localize_help_subcommand);
+  }
+  let has_help = |clap: &clap::Command| clap.get_subcommands().any(|sub| sub.get_name() == "help");
+  if !has_help(&clap) {
+    return clap;
+  }
+  clap.mut_subcommand("help", |help| {
+    let help = help.about(message("help-subcommand"));
+    match has_help(&help) {
+      true => help.mut_subcommand("help", |help| help.about(message("help-subcommand"))),
+      false => help,
+    }
+  })
+}
+
+/// Quote the value if it contains whitespaces, the same as clap does
+fn escape(value: &str) -> String {
+  match value.contains(char::is_whitespace) {
+    true => format!("{:?}", value),
+    false => value.to_string(),
+  }
+}
+
+fn write_values_list(
+  message: &mut String,
+  list_name: &str,
+  styles: &Styles,
+  values: Option<&ContextValue>,
+) {
+  let valid = styles.get_valid();
+  if let Some(ContextValue::Strings(values)) = values {
+    if !values.is_empty() {
+      let values = values
+        .iter()
+        .map(|value| format!("{valid}{}{valid:#}", escape(value)))
+        .collect::<Vec<_>>();
+      let _ = write!(
+        message,
+        "\n{TAB}[{}: {}]",
+        self::message(list_name),
+        values.join(", ")
+      );
+    }
+  }
+}
+
+fn write_similar(message: &mut String, styles: &Styles, name: &str, values: &ContextValue) {
+  let valid = styles.get_valid();
+  let (id, values) = match values {
+    ContextValue::String(value) => (name.to_string(), vec![value.as_str()]),
+    ContextValue::Strings(values) if values.len() == 1 => {
+      (name.to_string(), vec![values[0].as_str()])
+    }
+    ContextValue::Strings(values) => (
+      format!("{}s", name),
+      values.iter().map(String::as_str).collect(),
+    ),
+    _ => return,
+  };
+  let values = values
+    .iter()
+    .map(|value| format!("'{valid}{value}{valid:#}'"))
+    .collect::<Vec<_>>()
+    .join(", ");
+  let _ = write!(
+    message,
+    "{TAB}{valid}{}{valid:#} {}",
+    self::message("tip"),
+    format_message(&format!("similar-{}", id), &[("values", &values)])
+  );
+}
+
+/// Render the message of the error from its context, the same as clap does
+fn render_error_message(error: &clap::Error, styles: &Styles) -> Option<String> {
+  let valid = styles.get_valid();
+  let invalid = styles.get_invalid();
+  let literal = styles.get_literal();
+  let string = |kind| match error.get(kind) {
+    Some(ContextValue::String(value)) => Some(value.as_str()),
+    _ => None,
+  };
+  let number = |kind| match error.get(kind) {
+    Some(ContextValue::Number(value)) => Some(*value),
+    _ => None,
+  };
+  let arg = string(ContextKind::InvalidArg).map(|arg| format!("{invalid}{arg}{invalid:#}"));
+  let value = string(ContextKind::InvalidValue).map(|value| format!("{invalid}{value}{invalid:#}"));
+  let literal_arg = string(ContextKind::InvalidArg).map(|arg| format!("{literal}{arg}{literal:#}"));
+
+  let mut message = match error.kind() {
+    ErrorKind::ArgumentConflict => {
+      let arg = arg?;
+      match error.get(ContextKind::PriorArg) {
+        Some(ContextValue::String(prior))
+          if Some(prior.as_str()) == string(ContextKind::InvalidArg) =>
+        {
+          format_message("argument-repeated", &[("arg", &arg)])
+        }
+        Some(ContextValue::String(prior)) => format_message(
+          "argument-conflict",
+          &[
+            ("arg", &arg),
+            ("other", &format!("{invalid}{prior}{invalid:#}")),
+          ],
+        ),
+        Some(ContextValue::Strings(priors)) => {
+          let mut message = format_message("argument-conflicts", &[("arg", &arg)]);
+          for prior in priors {
+            let _ = write!(message, "\n{TAB}{invalid}{prior}{invalid:#}");
+          }
+          message
+        }
+        _ => format_message("argument-conflict-others", &[("arg", &arg)]),
+      }
+    }
+    ErrorKind::NoEquals => format_message("no-equals", &[("arg", &arg?)]),
+    ErrorKind::InvalidValue => {
+      let mut message = match string(ContextKind::InvalidValue)? {
+        "" => format_message("value-required", &[("arg", &arg?)]),
+        _ => format_message(
+          "invalid-value",
+          &[("arg", &literal_arg?), ("value", &value?)],
+        ),
+      };
+      write_values_list(
+        &mut message,
+        "possible-values",
+        styles,
+        error.get(ContextKind::ValidValue),
+      );
+      message
+    }
+    ErrorKind::InvalidSubcommand => {
+      let subcommand = string(ContextKind::InvalidSubcommand)?;
+      format_message(
+        "invalid-subcommand",
+        &[("subcommand", &format!("{invalid}{subcommand}{invalid:#}"))],
+      )
+    }
+    ErrorKind::MissingRequiredArgument => {
+      let Some(ContextValue::Strings(args)) = error.get(ContextKind::InvalidArg) else {
+        return None;
+      };
+      let mut message = self::message("missing-required-arguments");
+      for arg in args {
+        let _ = write!(message, "\n{TAB}{valid}{arg}{valid:#}");
+      }
+      message
+    }
+    ErrorKind::MissingSubcommand => {
+      let subcommand = string(ContextKind::InvalidSubcommand)?;
+      let mut message = format_message(
+        "missing-subcommand",
+        &[("subcommand", &format!("{invalid}{subcommand}{invalid:#}"))],
+      );
+      write_values_list(
+        &mut message,
+        "subcommands",
+        styles,
+        error.get(ContextKind::ValidSubcommand),
+      );
+      message
+    }
+    ErrorKind::TooManyValues => format_message(
+      "too-many-values",
+      &[("arg", &literal_arg?), ("value", &value?)],
+    ),
+    ErrorKind::TooFewValues | ErrorKind::WrongNumberOfValues => {
+      let (id, expected) = match error.kind() {
+        ErrorKind::TooFewValues => ("too-few-values", number(ContextKind::MinValues)?),
+        _ => (
+          "wrong-number-of-values",
+          number(ContextKind::ExpectedNumValues)?,
+        ),
+      };
+      let actual = number(ContextKind::ActualNumValues)?;
+      let id = match actual > 1 {
+        true => id.to_string(),
+        false => format!("{}-one", id),
+      };
+      format_message(
+        &id,
+        &[
+          ("arg", &literal_arg?),
+          ("expected", &format!("{valid}{expected}{valid:#}")),
+          ("actual", &format!("{invalid}{actual}{invalid:#}")),
+        ],
+      )
+    }
+    ErrorKind::ValueValidation => {
+      let mut message = format_message(
+        "invalid-value",
+        &[("arg", &literal_arg?), ("value", &value?)],
+      );
+      if let Some(source) = std::error::Error::source(error) {
+        let _ = write!(message, ": {}", source);
+      }
+      message
+    }
+    ErrorKind::UnknownArgument => format_message("unexpected-argument", &[("arg", &arg?)]),
+    _ => return None,
+  };
+
+  let mut suggested = false;
+  for (kind, name) in [
+    (ContextKind::SuggestedSubcommand, "subcommand"),
+    (ContextKind::SuggestedArg, "argument"),
+    (ContextKind::SuggestedValue, "value"),
+  ] {
+    if let Some(values) = error.get(kind) {
+      message.push('\n');
+      if !suggested {
+        message.push('\n');
+        suggested = true;
+      }
+      write_similar(&mut message, styles, name, values);
+    }
+  }
+  write_suggestions(&mut message, error, styles, suggested);
+  Some(message)
+}
+
+/// Write the tips of the `Suggested` context of the error
+fn write_suggestions(message: &mut String, error: &clap::Error, styles: &Styles, suggested: bool) {
+  let valid = styles.get_valid();
+  if let Some(ContextValue::StyledStrs(suggestions)) = error.get(ContextKind::Suggested) {
+    if !suggested {
+      message.push('\n');
+    }
+    for suggestion in suggestions {
+      let _ = write!(
+        message,
+        "\n{TAB}{valid}{}{valid:#} {}",
+        self::message("tip"),
+        suggestion.ansi()
+      );
+    }
+  }
+}
+
+/// Render the error with the localized messages
+///
+/// The message of the error is rendered from its context, while the usage
+/// and the `--help` tip rendered by clap are rebuilt with the localized
+/// messages. The messages of the other errors, e.g. of `ctx.fail`, are kept.
+///
+/// The tips of the errors with custom messages are not rendered by clap, so
+/// they are rendered here even if the messages are not localized.
+pub(crate) fn render_error(error: &clap::Error, styles: &Styles) -> StyledStr {
+  let is_custom = error.kind() == ErrorKind::Format;
+  let has_custom_tips = is_custom && error.get(ContextKind::Suggested).is_some();
+  if !(is_localized() || has_custom_tips)
+    || matches!(
+      error.kind(),
+      ErrorKind::DisplayHelp
+        | ErrorKind::DisplayVersion
+        | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+    )
+  {
+    return error.render();
+  }
+  let rendered = error.render().ansi().to_string();
+
+  // The usage and the `--help` tip follow the message after a blank line
+  let usage = match error.get(ContextKind::Usage) {
+    Some(ContextValue::StyledStr(usage)) => Some(usage.ansi().to_string()),
+    _ => None,
+  };
+  let usage_start = usage
+    .as_ref()
+    .and_then(|usage| rendered.find(&format!("\n\n{}", usage)));
+  let tip_prefix = "\n\nFor more information, try '";
+  let tip_start = rendered
+    .rfind(tip_prefix)
+    .filter(|_| rendered.ends_with("'.\n"));
+  let end = rendered.strip_suffix('\n').unwrap_or(&rendered).len();
+  let tail_start = usage_start.or(tip_start).unwrap_or(end);
+
+  let error_style = styles.get_error();
+  let message = match render_error_message(error, styles) {
+    Some(message) => message,
+    None => {
+      match rendered[..tail_start].strip_prefix(&format!("{error_style}error:{error_style:#} ")) {
+        Some(message) => {
+          let mut message = message.to_string();
+          if is_custom {
+            write_suggestions(&mut message, error, styles, false);
+          }
+          message
+        }
+        None => return error.render(),
+      }
+    }
+  };
+  let mut output = format!(
+    "{error_style}{}{error_style:#} {}",
+    self::message("error"),
+    message
+  );
+  if let Some(usage) = usage.filter(|_| usage_start.is_some()) {
+    let _ = write!(output, "\n\n{}", localize_usage_heading(&usage, styles));
+  }
+  if let Some(start) = tip_start {
+    let help = &rendered[start + tip_prefix.len()..rendered.len() - "'.\n".len()];
+    let _ = write!(
+      output,
+      "\n\n{}",
+      format_message("more-information", &[("help", help)])
+    );
+  }
+  output.push('\n');
+  StyledStr::from(output)
+}
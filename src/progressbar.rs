@@ -1,7 +1,9 @@
 use std::time::Duration;
 
+use clap::builder::styling::{AnsiColor, Color, Effects, Style};
 use napi_derive::napi;
 
+use crate::testing::{is_capturing, write, Stream};
 use crate::types::{Context, Error};
 
 #[napi]
@@ -9,6 +11,16 @@ pub struct ProgressBar {
   bar: indicatif::ProgressBar,
 }
 
+impl From<indicatif::ProgressBar> for ProgressBar {
+  fn from(bar: indicatif::ProgressBar) -> Self {
+    // Progress bars are not drawn while the output is captured
+    if is_capturing() {
+      bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+    }
+    ProgressBar { bar }
+  }
+}
+
 #[napi]
 impl ProgressBar {
   #[napi]
@@ -116,8 +128,12 @@ impl ProgressBar {
   }
 
   #[napi]
-  pub fn println(&self, msg: String) {
+  pub fn println(&self, msg: String) -> napi::Result<()> {
+    if is_capturing() {
+      return write(Stream::Stderr, &format!("{}\n", msg));
+    }
     self.bar.println(msg);
+    Ok(())
   }
 
   #[napi]
@@ -143,14 +159,79 @@ impl Context {
   #[napi]
   pub fn create_progress_bar(&self, total: u32) -> ProgressBar {
     let bar = indicatif::ProgressBar::new(total as u64);
-    ProgressBar { bar }
+    ProgressBar::from(self.apply_progress_theme(bar, "{wide_bar:.STYLE} {pos}/{len}"))
   }
 
   #[napi]
   pub fn create_spinner(&self) -> ProgressBar {
-    let bar = indicatif::ProgressBar::new_spinner();
-    ProgressBar { bar }
+    let spinner = indicatif::ProgressBar::new_spinner();
+    ProgressBar::from(self.apply_progress_theme(spinner, "{spinner:.STYLE} {msg}"))
+  }
+
+  /// Style the progress bar with the literal style of the theme, the `STYLE`
+  /// of the template is replaced by the style, and the colors of the
+  /// progress bars are enabled as the command does
+  fn apply_progress_theme(
+    &self,
+    bar: indicatif::ProgressBar,
+    template: &str,
+  ) -> indicatif::ProgressBar {
+    console::set_colors_enabled_stderr(self.is_colored(Stream::Stderr));
+    let Some(styles) = self.theme_styles(Stream::Stderr) else {
+      return bar;
+    };
+    let style = resolve_dotted_style(styles.get_literal());
+    if style.is_empty() {
+      return bar;
+    }
+    let template = template.replace("STYLE", &style);
+    bar.with_style(indicatif::ProgressStyle::with_template(&template).unwrap())
+  }
+}
+
+/// Convert the style to the dotted style string of the progress bar templates
+///
+/// The RGB colors and the italic effect are not supported by the progress bars.
+fn resolve_dotted_style(style: &Style) -> String {
+  let mut parts = Vec::new();
+  let mut push_color = |color: Option<Color>, prefix: &str| match color {
+    Some(Color::Ansi(color)) => {
+      let name = match color {
+        AnsiColor::Black | AnsiColor::BrightBlack => "black",
+        AnsiColor::Red | AnsiColor::BrightRed => "red",
+        AnsiColor::Green | AnsiColor::BrightGreen => "green",
+        AnsiColor::Yellow | AnsiColor::BrightYellow => "yellow",
+        AnsiColor::Blue | AnsiColor::BrightBlue => "blue",
+        AnsiColor::Magenta | AnsiColor::BrightMagenta => "magenta",
+        AnsiColor::Cyan | AnsiColor::BrightCyan => "cyan",
+        AnsiColor::White | AnsiColor::BrightWhite => "white",
+      };
+      parts.push(format!("{}{}", prefix, name));
+      if color.is_bright() {
+        parts.push(format!("{}bright", prefix));
+      }
+    }
+    Some(Color::Ansi256(color)) => parts.push(format!("{}{}", prefix, color.0)),
+    Some(Color::Rgb(_)) | None => {}
+  };
+  push_color(style.get_fg_color(), "");
+  push_color(style.get_bg_color(), "on_");
+
+  let effects = style.get_effects();
+  for (effect, name) in [
+    (Effects::BOLD, "bold"),
+    (Effects::DIMMED, "dim"),
+    (Effects::UNDERLINE, "underlined"),
+    (Effects::BLINK, "blink"),
+    (Effects::INVERT, "reverse"),
+    (Effects::HIDDEN, "hidden"),
+    (Effects::STRIKETHROUGH, "strikethrough"),
+  ] {
+    if effects.contains(effect) {
+      parts.push(name.to_string());
+    }
   }
+  parts.join(".")
 }
 
 /// Creates a new progress bar with the specified total number of steps.
@@ -164,8 +245,7 @@ impl Context {
 /// A new `ProgressBar` instance.
 #[napi]
 pub fn create_progress_bar(total: u32) -> ProgressBar {
-  let bar = indicatif::ProgressBar::new(total as u64);
-  ProgressBar { bar }
+  ProgressBar::from(indicatif::ProgressBar::new(total as u64))
 }
 
 /// Creates a new spinner progress bar.
@@ -175,6 +255,5 @@ pub fn create_progress_bar(total: u32) -> ProgressBar {
 /// A new `ProgressBar` instance with a spinner style.
 #[napi]
 pub fn create_spinner() -> ProgressBar {
-  let bar = indicatif::ProgressBar::new_spinner();
-  ProgressBar { bar }
+  ProgressBar::from(indicatif::ProgressBar::new_spinner())
 }
@@ -0,0 +1,369 @@
+use std::io::Write;
+
+use inquire::autocompletion::{Autocomplete, Replacement};
+use inquire::{CustomUserError, InquireError};
+use napi::bindgen_prelude::FromNapiValue;
+use napi::{CallContext, Env, JsObject, JsUnknown, NapiRaw, Result};
+use napi_derive::{js_function, napi};
+
+use crate::command::{execute, settle_run};
+use crate::hooks::{bind, is_thenable, share, then};
+use crate::locale::{format_message, localize_command, message, set_locale};
+use crate::resolver::{display_negatable_flags, resolve_command};
+use crate::testing::{answer_line, is_colored, take_exception, write, Stream};
+use crate::types::{Command, Error};
+use crate::utils::split_words;
+
+/// Options of `repl`
+#[napi(object)]
+#[derive(Default)]
+pub struct ReplOptions {
+  /// Prompt displayed before each line, defaults to `<name>>`
+  pub prompt: Option<String>,
+  /// Path of the history file, the lines are loaded from the file on start
+  /// and appended to the file after they are entered
+  pub history: Option<String>,
+}
+
+/// Tab completion of the subcommands and options from the command tree,
+/// and suggestions of the previous lines from the history
+#[derive(Clone)]
+struct Completer {
+  clap: clap::Command,
+  history: Vec<String>,
+}
+
+impl Completer {
+  /// Candidates of the last word of the input
+  fn candidates(&self, input: &str) -> Vec<String> {
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+    if input.is_empty() || input.ends_with(char::is_whitespace) {
+      words.push("");
+    }
+    let Some((word, path)) = words.split_last() else {
+      return Vec::new();
+    };
+    let mut current = &self.clap;
+    for name in path.iter().filter(|name| !name.starts_with('-')) {
+      match current.find_subcommand(name) {
+        Some(subcommand) => current = subcommand,
+        None => break,
+      }
+    }
+
+    let mut candidates: Vec<String> = match word.starts_with('-') {
+      true => current
+        .get_arguments()
+        .filter_map(|arg| arg.get_long())
+        .map(|long| format!("--{}", long))
+        .collect(),
+      false => current
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect(),
+    };
+    if path.is_empty() {
+      candidates.extend(["help".to_string(), "exit".to_string()]);
+    }
+    candidates.retain(|candidate| candidate.starts_with(word));
+    candidates.sort();
+    candidates.dedup();
+    candidates
+  }
+}
+
+impl Autocomplete for Completer {
+  fn get_suggestions(&mut self, input: &str) -> std::result::Result<Vec<String>, CustomUserError> {
+    if input.is_empty() {
+      return Ok(Vec::new());
+    }
+    let mut suggestions: Vec<String> = Vec::new();
+    for line in self.history.iter().rev() {
+      if line.starts_with(input) && line != input && !suggestions.contains(line) {
+        suggestions.push(line.clone());
+      }
+    }
+    Ok(suggestions)
+  }
+
+  fn get_completion(
+    &mut self,
+    input: &str,
+    highlighted_suggestion: Option<String>,
+  ) -> std::result::Result<Replacement, CustomUserError> {
+    if highlighted_suggestion.is_some() {
+      return Ok(highlighted_suggestion);
+    }
+    let candidates = self.candidates(input);
+    let Some(first) = candidates.first() else {
+      return Ok(None);
+    };
+    let word_start = input
+      .rfind(char::is_whitespace)
+      .map_or(0, |index| index + 1);
+    if candidates.len() == 1 {
+      return Ok(Some(format!("{}{} ", &input[..word_start], first)));
+    }
+    // Complete the longest common prefix of the candidates
+    let prefix = candidates
+      .iter()
+      .skip(1)
+      .fold(first.as_str(), |prefix, candidate| {
+        let len = prefix
+          .char_indices()
+          .zip(candidate.chars())
+          .take_while(|((_, a), b)| a == b)
+          .last()
+          .map_or(0, |((index, c), _)| index + c.len_utf8());
+        &prefix[..len]
+      });
+    Ok(Some(format!("{}{}", &input[..word_start], prefix)))
+  }
+}
+
+fn resolve_clap(cmd: &Command) -> Result<clap::Command> {
+  set_locale(&cmd.meta)?;
+  let mut clap = resolve_command(
+    clap::Command::default(),
+    Default::default(),
+    cmd,
+    &Default::default(),
+  )?
+  .no_binary_name(true);
+  clap.build();
+  Ok(localize_command(clap))
+}
+
+fn get_command(env: Env, session: &JsObject) -> Result<Command> {
+  let cmd: JsObject = session.get_named_property("cmd")?;
+  unsafe { Command::from_napi_value(env.raw(), cmd.raw()) }
+}
+
+/// Read the next line, returns `None` when the REPL ends
+fn read_line(session: &JsObject, clap: &clap::Command) -> Result<Option<String>> {
+  let prompt: String = session.get_named_property("prompt")?;
+  if let Some(line) = answer_line(&prompt)? {
+    return Ok(line);
+  }
+  let history: Vec<String> = session.get_named_property("history")?;
+  let completer = Completer {
+    clap: clap.clone(),
+    history,
+  };
+  match inquire::Text::new(&prompt)
+    .with_autocomplete(completer)
+    .prompt()
+  {
+    Ok(line) => Ok(Some(line)),
+    Err(InquireError::OperationCanceled) => Ok(Some(String::new())),
+    Err(InquireError::OperationInterrupted) => Ok(None),
+    Err(err) => Err(Error::InquireError(err).into()),
+  }
+}
+
+/// Keep the line in the history and append it to the history file
+fn remember(session: &mut JsObject, line: &str) -> Result<()> {
+  let mut history: Vec<String> = session.get_named_property("history")?;
+  if history.last().map(String::as_str) == Some(line) {
+    return Ok(());
+  }
+  history.push(line.to_string());
+  session.set_named_property("history", history)?;
+  let file: Option<String> = session.get_named_property("file")?;
+  if let Some(file) = file {
+    std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(file)
+      .and_then(|mut file| writeln!(file, "{}", line))
+      .map_err(|err| napi::Error::from_reason(err.to_string()))?;
+  }
+  Ok(())
+}
+
+/// Print the help of the root command or the given subcommand
+fn print_help(clap: &clap::Command, path: &[String]) -> Result<()> {
+  let mut current = clap;
+  for name in path {
+    match current.find_subcommand(name) {
+      Some(subcommand) => current = subcommand,
+      None => {
+        return write(
+          Stream::Stderr,
+          &format!(
+            "{} {}\n",
+            message("error"),
+            format_message("unknown-command", &[("name", name)])
+          ),
+        )
+      }
+    }
+  }
+  let help = display_negatable_flags(current.clone()).render_help();
+  match is_colored(clap, Stream::Stdout) {
+    true => write(Stream::Stdout, &help.ansi().to_string()),
+    false => write(Stream::Stdout, &help.to_string()),
+  }
+}
+
+/// Print the error thrown by a line, the REPL keeps running after errors
+fn print_error(error: Option<JsUnknown>, reason: &str) -> Result<()> {
+  let message = match error {
+    Some(error) => {
+      let message = match error.get_type()? {
+        napi::ValueType::Object => {
+          unsafe { error.cast::<JsObject>() }.get_named_property("message")?
+        }
+        _ => error,
+      };
+      message
+        .coerce_to_string()?
+        .into_utf8()?
+        .as_str()?
+        .to_string()
+    }
+    None => reason.to_string(),
+  };
+  write(
+    Stream::Stderr,
+    &format!("{} {}\n", self::message("error"), message),
+  )
+}
+
+/// Restore the exit code of the process, the failed lines do not fail the REPL
+fn restore_exit_code(env: Env, session: &JsObject) -> Result<()> {
+  let mut process: JsObject = env.get_global()?.get_named_property("process")?;
+  let exit_code: JsUnknown = session.get_named_property("exitCode")?;
+  process.set_named_property("exitCode", exit_code)
+}
+
+/// Read, split and run the lines until `exit`, or until an async line
+/// returns a `Promise`, which resumes the REPL when it is settled
+fn read_eval(env: Env, mut session: JsObject) -> Result<JsUnknown> {
+  let clap = resolve_clap(&get_command(env, &session)?)?;
+  while let Some(line) = read_line(&session, &clap)? {
+    let words = match split_words(&line) {
+      Ok(words) => words,
+      Err(err) => {
+        print_error(None, &err.reason)?;
+        continue;
+      }
+    };
+    let Some(first) = words.first() else {
+      continue;
+    };
+    remember(&mut session, line.trim())?;
+    match first.as_str() {
+      "exit" => break,
+      "help" => {
+        print_help(&clap, &words[1..])?;
+        continue;
+      }
+      _ => {}
+    }
+
+    let mut raw_args = vec![clap.get_name().to_string()];
+    raw_args.extend(words);
+    let cmd = get_command(env, &session)?;
+    let state = session.get_named_property("state")?;
+    let value = match execute(env, &clap, cmd, raw_args, false, Some(state)) {
+      Ok(value) => value,
+      Err(err) => {
+        print_error(take_exception(env), &err.reason)?;
+        restore_exit_code(env, &session)?;
+        continue;
+      }
+    };
+    if is_thenable(&value)? {
+      let on_fulfilled = bind(
+        env,
+        env.create_function("resume", resume_repl)?,
+        vec![share(env, &session)],
+      )?;
+      let on_rejected = bind(
+        env,
+        env.create_function("recover", recover_repl)?,
+        vec![share(env, &session)],
+      )?;
+      return then(value, on_fulfilled, Some(on_rejected));
+    }
+    restore_exit_code(env, &session)?;
+  }
+  Ok(env.get_undefined()?.into_unknown())
+}
+
+/// Continuation of the REPL after an async line is fulfilled
+///
+/// Arguments: the REPL session.
+#[js_function(1)]
+fn resume_repl(ctx: CallContext) -> Result<JsUnknown> {
+  let session = ctx.get::<JsObject>(0)?;
+  restore_exit_code(*ctx.env, &session)?;
+  read_eval(*ctx.env, session)
+}
+
+/// Continuation of the REPL after an async line is rejected
+///
+/// Arguments: the REPL session and the error of the line.
+#[js_function(2)]
+fn recover_repl(ctx: CallContext) -> Result<JsUnknown> {
+  let session = ctx.get::<JsObject>(0)?;
+  let error = ctx.get::<JsUnknown>(1)?;
+  print_error(Some(error), "")?;
+  restore_exit_code(*ctx.env, &session)?;
+  read_eval(*ctx.env, session)
+}
+
+/// Start an interactive shell over the command tree
+///
+/// Each line is split with the shell quoting rules and run as the arguments
+/// of the command without the binary name, e.g. `dev --port 3000` runs the
+/// `dev` subcommand. Parse errors and errors thrown by the lines are printed
+/// instead of ending the REPL.
+///
+/// The subcommands and options are completed with `tab`, and the previous
+/// lines are suggested while typing. The built-in `help [command]` prints the
+/// help of the command, and `exit` or `Ctrl-C` ends the REPL.
+///
+/// **NOTE**: If a line returns a `Promise`, the REPL continues after it is
+/// settled and a `Promise` is returned instead.
+///
+/// @param cmd Command object
+/// @param options REPL options
+/// @returns {void | Promise<void>}
+#[napi(
+  ts_args_type = "cmd: Command, options?: ReplOptions",
+  ts_return_type = "void | Promise<void>"
+)]
+pub fn repl(env: Env, cmd: JsObject, options: Option<ReplOptions>) -> Result<JsUnknown> {
+  settle_run(env, start_repl(env, cmd, options.unwrap_or_default()))
+}
+
+fn start_repl(env: Env, cmd: JsObject, options: ReplOptions) -> Result<JsUnknown> {
+  let clap = resolve_clap(&unsafe { Command::from_napi_value(env.raw(), cmd.raw()) }?)?;
+  let history = match &options.history {
+    Some(file) => std::fs::read_to_string(file)
+      .map(|content| content.lines().map(|line| line.to_string()).collect())
+      .unwrap_or_default(),
+    None => Vec::<String>::new(),
+  };
+  let process: JsObject = env.get_global()?.get_named_property("process")?;
+
+  let mut session = env.create_object()?;
+  session.set_named_property("cmd", cmd)?;
+  session.set_named_property(
+    "prompt",
+    options
+      .prompt
+      .unwrap_or_else(|| format!("{}>", clap.get_name())),
+  )?;
+  // The state is shared by the lines of the session
+  session.set_named_property("state", env.create_object()?)?;
+  session.set_named_property("history", history)?;
+  session.set_named_property("file", options.history)?;
+  session.set_named_property(
+    "exitCode",
+    process.get_named_property::<JsUnknown>("exitCode")?,
+  )?;
+  read_eval(env, session)
+}
@@ -1,7 +1,16 @@
+use std::fmt::Write;
+
+use clap::builder::styling::{Ansi256Color, AnsiColor, Color, Effects, RgbColor, Style, Styles};
+use clap::builder::StyledStr;
+use napi::Either;
+
 use crate::{
-  apply_opt, to_char_vec,
-  types::{Command, CommandMeta, CommandOption},
-  utils::{leak_borrowed_str, leak_borrowed_str_or_default, leak_str},
+  apply_opt,
+  inquire::is_promptable,
+  locale::{help_flag, help_template, is_localized, localize_arg, message},
+  to_char_vec,
+  types::{Command, CommandMeta, CommandOption, Error, Theme, ThemeStyle},
+  utils::{as_usize, leak_borrowed_str, leak_str, to_kebab_case, NEGATION_ID_PREFIX},
   HashMap,
 };
 
@@ -10,6 +19,12 @@ pub(crate) fn resolve_option_args(
   args: Option<Vec<String>>,
 ) -> napi::Result<Vec<String>> {
   if let Some(mut args) = args {
+    if args.is_empty() {
+      return Err(napi::Error::new(
+        napi::Status::InvalidArg,
+        "The arguments must start with the executable, e.g. `['node', 'cli.js']`",
+      ));
+    }
     args.remove(0);
     return Ok(args);
   }
@@ -32,7 +47,8 @@ pub(crate) fn resolve_command_meta(
   mut clap: clap::Command,
   bin_name: Option<String>,
   meta: &CommandMeta,
-) -> clap::Command {
+  inherited: &Inherited,
+) -> napi::Result<clap::Command> {
   let name: &'static str = meta.name.as_ref().map_or_else(
     || leak_str(bin_name.expect("bin_name must be provided")),
     |name| leak_borrowed_str(name),
@@ -40,20 +56,261 @@ pub(crate) fn resolve_command_meta(
   clap = clap.name(name);
 
   apply_opt!(clap, meta, leak_borrowed_str(&version) => version);
+  apply_opt!(clap, meta, leak_borrowed_str(&long_version) => long_version);
+  apply_opt!(clap, meta, propagate_version);
   apply_opt!(clap, meta, leak_borrowed_str(&about) => about);
   apply_opt!(clap, meta, subcommand_required);
+  apply_opt!(clap, meta, multicall);
 
-  if meta.styled.unwrap_or(false) {
-    use clap::builder::styling;
-    let styles = styling::Styles::styled()
-      .header(styling::AnsiColor::Green.on_default() | styling::Effects::BOLD)
-      .usage(styling::AnsiColor::Green.on_default() | styling::Effects::BOLD)
-      .literal(styling::AnsiColor::Cyan.on_default() | styling::Effects::BOLD)
-      .placeholder(styling::AnsiColor::Cyan.on_default());
-    clap = clap.styles(styles);
+  match &meta.theme {
+    Some(Either::A(preset)) => clap = clap.styles(resolve_preset(preset)),
+    Some(Either::B(theme)) => clap = clap.styles(resolve_theme(theme)),
+    None if meta.styled.unwrap_or(false) => clap = clap.styles(resolve_preset("styled")),
+    None => {}
+  }
+  apply_opt!(clap, meta, resolve_color_choice(&color) => color);
+  if is_localized() {
+    // The styles of the parent commands override the styles of the subcommands
+    let template = help_template(inherited.styles.as_ref().unwrap_or(clap.get_styles()));
+    clap = clap
+      .help_template(template)
+      .subcommand_help_heading(leak_str(message("commands-heading")));
   }
 
-  clap
+  apply_opt!(clap, meta, leak_borrowed_str(&long_about) => long_about);
+  apply_opt!(clap, meta, leak_borrowed_str(&author) => author);
+  apply_opt!(clap, meta, leak_borrowed_str(&before_help) => before_help);
+  apply_opt!(clap, meta, leak_borrowed_str(&before_long_help) => before_long_help);
+  apply_opt!(clap, meta, leak_borrowed_str(&help_template) => help_template);
+  apply_opt!(clap, meta, leak_borrowed_str(&usage) => override_usage);
+  apply_opt!(clap, meta, next_line_help);
+  match &meta.examples {
+    Some(examples) => {
+      let section = resolve_examples(&clap, examples);
+      clap = clap.after_help(append_help(section.clone(), &meta.after_help));
+      if meta.after_long_help.is_some() {
+        clap = clap.after_long_help(append_help(section, &meta.after_long_help));
+      }
+    }
+    None => {
+      apply_opt!(clap, meta, leak_borrowed_str(&after_help) => after_help);
+      apply_opt!(clap, meta, leak_borrowed_str(&after_long_help) => after_long_help);
+    }
+  }
+
+  Ok(clap)
+}
+
+/// Replace the built-in help and version flags, so that they are localized
+/// or named as customized
+fn resolve_builtin_flags(
+  mut clap: clap::Command,
+  cmd: &Command,
+  inherited: &Inherited,
+) -> napi::Result<clap::Command> {
+  let mut version_flag = resolve_version_flag(&cmd.meta, inherited)?;
+  if is_localized() {
+    version_flag = version_flag.or(Some(VersionFlag {
+      long: "version",
+      short: Some('V'),
+    }));
+    // The built-in flag is disabled for the subcommands as well, and the
+    // multicall command has no flags to replace
+    if !clap.is_multicall_set() {
+      clap = clap
+        .disable_help_flag(true)
+        .arg(help_flag(has_long_help(cmd, inherited)));
+    }
+  }
+  if let Some(flag) = version_flag {
+    clap = clap.disable_version_flag(true);
+    if has_version(&cmd.meta, inherited) && !clap.is_multicall_set() {
+      clap = clap.arg(
+        clap::Arg::new("version")
+          .long(flag.long)
+          .short(flag.short)
+          .action(clap::ArgAction::Version)
+          .help(message("version-flag")),
+      );
+    }
+  }
+  Ok(clap)
+}
+
+/// Whether an option makes the long help differ from the short help
+fn has_long_option_help(opt: &CommandOption) -> bool {
+  !opt.hidden.unwrap_or(false) && (opt.long_help.is_some() || opt.hide_short_help.unwrap_or(false))
+}
+
+/// Whether the command has the long help, checked the same as clap does
+fn has_long_help(cmd: &Command, inherited: &Inherited) -> bool {
+  let meta = &cmd.meta;
+  inherited.long_help
+    || meta.long_about.is_some()
+    || meta.before_long_help.is_some()
+    || meta.after_long_help.is_some()
+    || cmd.options.values().any(has_long_option_help)
+}
+
+/// Render the examples as a help section with the header style of the command
+fn resolve_examples(clap: &clap::Command, examples: &[String]) -> StyledStr {
+  let header = clap.get_styles().get_header();
+  let mut section = StyledStr::new();
+  let _ = write!(
+    section,
+    "{}{}:{:#}",
+    header,
+    message("examples-heading"),
+    header
+  );
+  for example in examples {
+    let _ = write!(section, "\n  {}", example);
+  }
+  section
+}
+
+fn append_help(mut section: StyledStr, text: &Option<String>) -> StyledStr {
+  if let Some(text) = text {
+    let _ = write!(section, "\n\n{}", text);
+  }
+  section
+}
+
+pub(crate) fn resolve_color_choice(color: &str) -> clap::ColorChoice {
+  match color {
+    "auto" => clap::ColorChoice::Auto,
+    "always" => clap::ColorChoice::Always,
+    "never" => clap::ColorChoice::Never,
+    _ => panic!("Unsupported color: {:?}", color),
+  }
+}
+
+pub(crate) fn resolve_preset(preset: &str) -> Styles {
+  match preset {
+    "default" => Styles::default(),
+    "styled" => Styles::styled()
+      .header(AnsiColor::Green.on_default() | Effects::BOLD)
+      .usage(AnsiColor::Green.on_default() | Effects::BOLD)
+      .literal(AnsiColor::Cyan.on_default() | Effects::BOLD)
+      .placeholder(AnsiColor::Cyan.on_default()),
+    "plain" => Styles::plain(),
+    _ => panic!("Unsupported theme preset: {:?}", preset),
+  }
+}
+
+pub(crate) fn resolve_theme(theme: &Theme) -> Styles {
+  let mut styles = resolve_preset(theme.preset.as_deref().unwrap_or("default"));
+  apply_opt!(styles, theme, resolve_style(&header) => header);
+  apply_opt!(styles, theme, resolve_style(&usage) => usage);
+  apply_opt!(styles, theme, resolve_style(&literal) => literal);
+  apply_opt!(styles, theme, resolve_style(&placeholder) => placeholder);
+  apply_opt!(styles, theme, resolve_style(&error) => error);
+  apply_opt!(styles, theme, resolve_style(&valid) => valid);
+  apply_opt!(styles, theme, resolve_style(&invalid) => invalid);
+  styles
+}
+
+fn resolve_style(style: &ThemeStyle) -> Style {
+  let effects = style
+    .effects
+    .iter()
+    .flatten()
+    .fold(Effects::new(), |effects, effect| {
+      effects
+        | match effect.as_str() {
+          "bold" => Effects::BOLD,
+          "dimmed" => Effects::DIMMED,
+          "italic" => Effects::ITALIC,
+          "underline" => Effects::UNDERLINE,
+          "blink" => Effects::BLINK,
+          "invert" => Effects::INVERT,
+          "hidden" => Effects::HIDDEN,
+          "strikethrough" => Effects::STRIKETHROUGH,
+          _ => panic!("Unsupported effect: {:?}", effect),
+        }
+    });
+  Style::new()
+    .fg_color(style.color.as_deref().map(resolve_color))
+    .bg_color(style.background.as_deref().map(resolve_color))
+    .effects(effects)
+}
+
+fn resolve_color(color: &str) -> Color {
+  let ansi = match color {
+    "black" => AnsiColor::Black,
+    "red" => AnsiColor::Red,
+    "green" => AnsiColor::Green,
+    "yellow" => AnsiColor::Yellow,
+    "blue" => AnsiColor::Blue,
+    "magenta" => AnsiColor::Magenta,
+    "cyan" => AnsiColor::Cyan,
+    "white" => AnsiColor::White,
+    "bright-black" => AnsiColor::BrightBlack,
+    "bright-red" => AnsiColor::BrightRed,
+    "bright-green" => AnsiColor::BrightGreen,
+    "bright-yellow" => AnsiColor::BrightYellow,
+    "bright-blue" => AnsiColor::BrightBlue,
+    "bright-magenta" => AnsiColor::BrightMagenta,
+    "bright-cyan" => AnsiColor::BrightCyan,
+    "bright-white" => AnsiColor::BrightWhite,
+    _ => {
+      if let Ok(index) = color.parse::<u8>() {
+        return Color::Ansi256(Ansi256Color(index));
+      }
+      let rgb = color
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .unwrap_or_else(|| panic!("Unsupported color: {:?}", color));
+      return Color::Rgb(RgbColor((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+  };
+  Color::Ansi(ansi)
+}
+
+/// Error kinds accepted by `ctx.error`
+const ERROR_KINDS: [(&str, clap::error::ErrorKind); 13] = {
+  use clap::error::ErrorKind;
+
+  [
+    ("invalid-value", ErrorKind::InvalidValue),
+    ("unknown-argument", ErrorKind::UnknownArgument),
+    ("invalid-subcommand", ErrorKind::InvalidSubcommand),
+    ("no-equals", ErrorKind::NoEquals),
+    ("value-validation", ErrorKind::ValueValidation),
+    ("too-many-values", ErrorKind::TooManyValues),
+    ("too-few-values", ErrorKind::TooFewValues),
+    ("wrong-number-of-values", ErrorKind::WrongNumberOfValues),
+    ("argument-conflict", ErrorKind::ArgumentConflict),
+    (
+      "missing-required-argument",
+      ErrorKind::MissingRequiredArgument,
+    ),
+    ("missing-subcommand", ErrorKind::MissingSubcommand),
+    ("io", ErrorKind::Io),
+    ("format", ErrorKind::Format),
+  ]
+};
+
+pub(crate) fn resolve_error_kind(kind: &str) -> napi::Result<clap::error::ErrorKind> {
+  ERROR_KINDS
+    .iter()
+    .find(|(name, _)| *name == kind)
+    .map(|(_, kind)| *kind)
+    .ok_or_else(|| {
+      let kinds = ERROR_KINDS
+        .iter()
+        .map(|(name, _)| format!("`{}`", name))
+        .collect::<Vec<_>>();
+      napi::Error::new(
+        napi::Status::InvalidArg,
+        format!(
+          "Unsupported error kind `{}`, expected one of {}",
+          kind,
+          kinds.join(", ")
+        ),
+      )
+    })
 }
 
 pub(crate) fn resolve_action(
@@ -64,7 +321,7 @@ pub(crate) fn resolve_action(
   let r#type = r#type.as_deref().unwrap_or("option");
   match action.as_deref() {
     Some("set") => clap::ArgAction::Set,
-    Some("append") => clap::ArgAction::Append,
+    Some("append") | Some("map") => clap::ArgAction::Append,
     Some("count") => clap::ArgAction::Count,
     Some("store") => clap::ArgAction::SetTrue,
     Some("store_false") => clap::ArgAction::SetFalse,
@@ -100,6 +357,31 @@ pub(crate) fn resolve_parser(
   }
 }
 
+pub(crate) fn resolve_map_parser(opt: &CommandOption) -> clap::builder::ValueParser {
+  let separator = opt.map_separator.clone().unwrap_or("=".to_string());
+  let parser = opt.parser.clone().unwrap_or("string".to_string());
+  clap::builder::ValueParser::new(move |value: &str| -> Result<(String, String), String> {
+    let (key, value) = value
+      .split_once(separator.as_str())
+      .ok_or_else(|| format!("expected `KEY{}VALUE`", separator))?;
+    let (key, value) = (key.trim(), value.trim());
+    if key.is_empty() || key.contains(char::is_whitespace) {
+      return Err(format!("invalid key `{}`", key));
+    }
+    match parser.as_str() {
+      "string" => {}
+      "number" => {
+        value.parse::<i64>().map_err(|err| err.to_string())?;
+      }
+      "boolean" => {
+        value.parse::<bool>().map_err(|err| err.to_string())?;
+      }
+      _ => panic!("Unsupported parser: {:?}", parser),
+    }
+    Ok((key.to_string(), value.to_string()))
+  })
+}
+
 pub(crate) fn resolve_value_hint(value_hint: &str) -> clap::builder::ValueHint {
   match value_hint {
     "any_path" => clap::builder::ValueHint::AnyPath,
@@ -158,61 +440,720 @@ pub(crate) fn resolve_num_args(num_args: &str) -> clap::builder::ValueRange {
   }
 }
 
+pub(crate) fn resolve_option_name(name: &str, rename_all: Option<&str>) -> &'static str {
+  match rename_all {
+    Some("kebab-case") => leak_str(to_kebab_case(name)),
+    Some("verbatim") | None => leak_borrowed_str(name),
+    _ => panic!("Unsupported rename_all: {:?}", rename_all),
+  }
+}
+
+/// Settings inherited from the parent commands when resolving subcommands
+#[derive(Default, Clone)]
+pub(crate) struct Inherited<'a> {
+  pub(crate) rename_all: Option<&'a str>,
+  /// Short option names taken by the global options, mapped to their long names
+  pub(crate) shorts: HashMap<char, String>,
+  /// Whether the missing options are prompted instead of failing
+  pub(crate) interactive: bool,
+  /// Customized version flag of the parent commands
+  pub(crate) version_flag: Option<VersionFlag>,
+  /// Whether the parent commands propagate their versions
+  pub(crate) propagate_version: bool,
+  /// Whether a version of the parent commands is propagated to the command
+  pub(crate) version: bool,
+  /// Styles of the parent commands, which override the styles of the command
+  pub(crate) styles: Option<Styles>,
+  /// Whether the global options of the parent commands have the long help
+  pub(crate) long_help: bool,
+}
+
+/// Names of a customized version flag
+#[derive(Clone, Copy)]
+pub(crate) struct VersionFlag {
+  pub(crate) long: &'static str,
+  pub(crate) short: Option<char>,
+}
+
+/// Resolve the customized version flag of the command, or `None` if the
+/// built-in `-V/--version` flag is used
+pub(crate) fn resolve_version_flag(
+  meta: &CommandMeta,
+  inherited: &Inherited,
+) -> napi::Result<Option<VersionFlag>> {
+  if meta.version_flag.is_none() && meta.version_short.is_none() {
+    return Ok(inherited.version_flag);
+  }
+  let parent = inherited.version_flag;
+  let long = match &meta.version_flag {
+    Some(long) => leak_borrowed_str(long),
+    None => parent.map_or("version", |flag| flag.long),
+  };
+  let short = match &meta.version_short {
+    Some(Either::A(short)) => Some(short.chars().next().ok_or_else(|| {
+      Error::DefinitionError(format!("Short name of version flag `--{}` is empty", long))
+    })?),
+    Some(Either::B(true)) => Some('V'),
+    Some(Either::B(false)) => None,
+    None => parent.map_or(Some('V'), |flag| flag.short),
+  };
+  Ok(Some(VersionFlag { long, short }))
+}
+
+/// Whether the command accepts the version flag
+pub(crate) fn has_version(meta: &CommandMeta, inherited: &Inherited) -> bool {
+  meta.version.is_some() || meta.long_version.is_some() || inherited.version
+}
+
+pub(crate) fn resolve_long_name(
+  name: &str,
+  opt: &CommandOption,
+  rename_all: Option<&str>,
+) -> &'static str {
+  opt.long.as_ref().map_or_else(
+    || resolve_option_name(name, rename_all),
+    |long| leak_borrowed_str(long),
+  )
+}
+
+/// Assign the short option names of a command.
+///
+/// Explicit short option names (and short aliases) colliding with each other,
+/// with the built-in `-h`/`-V` flags or with the global options of the parent
+/// commands are definition errors. Short option names derived from the long
+/// names are assigned in alphabetical order of the option keys, and disabled
+/// if they are already taken.
+pub(crate) fn resolve_short_names<'a>(
+  command_name: &str,
+  cmd: &'a Command,
+  inherited: &Inherited,
+) -> napi::Result<HashMap<&'a str, char>> {
+  let rename_all = cmd.meta.rename_all.as_deref().or(inherited.rename_all);
+  let mut taken = inherited.shorts.clone();
+  taken.insert('h', "--help".to_string());
+  if has_version(&cmd.meta, inherited) {
+    let flag = resolve_version_flag(&cmd.meta, inherited)?;
+    let long = flag.map_or("version", |flag| flag.long);
+    if let Some(short) = flag.map_or(Some('V'), |flag| flag.short) {
+      taken.insert(short, format!("--{}", long));
+    }
+  }
+
+  let mut options = cmd
+    .options
+    .iter()
+    .filter(|(_, opt)| opt.r#type.as_deref() != Some("positional"))
+    .collect::<Vec<_>>();
+  options.sort_by_key(|(name, _)| name.as_str());
+
+  let mut shorts = HashMap::default();
+  for (name, opt) in &options {
+    let long = resolve_long_name(name, opt, rename_all);
+    let explicit = match &opt.short {
+      Some(Either::A(short)) => Some(short.chars().next().ok_or_else(|| {
+        Error::DefinitionError(format!("Short option name of `{}` is empty", name))
+      })?),
+      _ => None,
+    };
+    let aliases = opt
+      .short_alias
+      .iter()
+      .chain(opt.hidden_short_alias.iter())
+      .flatten()
+      .filter_map(|alias| alias.chars().next());
+    for short in explicit.into_iter().chain(aliases) {
+      if let Some(owner) = taken.get(&short) {
+        return Err(
+          Error::DefinitionError(format!(
+            "Short option `-{}` of `--{}` conflicts with `{}` in command `{}`",
+            short,
+            long,
+            owner,
+            cmd.meta.name.as_deref().unwrap_or(command_name)
+          ))
+          .into(),
+        );
+      }
+      taken.insert(short, format!("--{}", long));
+    }
+    if let Some(short) = explicit {
+      shorts.insert(name.as_str(), short);
+    }
+  }
+
+  for (name, opt) in &options {
+    // Namespaced options have no default short option name
+    let derived = match &opt.short {
+      None | Some(Either::B(true)) => !name.contains('.'),
+      _ => false,
+    };
+    if !derived {
+      continue;
+    }
+    let long = resolve_long_name(name, opt, rename_all);
+    if let Some(short) = long
+      .chars()
+      .next()
+      .filter(|short| !taken.contains_key(short))
+    {
+      taken.insert(short, format!("--{}", long));
+      shorts.insert(name.as_str(), short);
+    }
+  }
+
+  Ok(shorts)
+}
+
 pub(crate) fn resolve_command_options(
-  clap: clap::Command,
+  mut clap: clap::Command,
   meta: &HashMap<String, CommandOption>,
+  rename_all: Option<&str>,
+  shorts: &HashMap<&str, char>,
+  interactive: bool,
+  long_help: bool,
 ) -> clap::Command {
-  clap.args(
+  // Options can be referred by either the key or the renamed name
+  let resolve_id = |id: &'static str| -> &'static str {
+    if meta.contains_key(id) {
+      return id;
+    }
     meta
-      .iter()
-      .map(|(name, opt)| {
-        let mut arg = clap::Arg::new(leak_borrowed_str(name));
-        arg = arg.action(resolve_action(&opt.action, &opt.r#type, &opt.parser));
-        if opt.r#type.as_deref() != Some("positional") {
-          let long = leak_borrowed_str_or_default(opt.long.as_ref(), name);
-          arg = arg.long(long).short(
-            leak_borrowed_str_or_default(opt.short.as_ref(), long)
-              .chars()
-              .next(),
+      .keys()
+      .find(|name| resolve_option_name(name, rename_all) == id)
+      .map_or(id, |name| leak_borrowed_str(name))
+  };
+  let mut args = meta
+    .iter()
+    .flat_map(|(name, opt)| {
+      let mut arg = clap::Arg::new(leak_borrowed_str(name));
+      let mut negation = None;
+      arg = arg.action(resolve_action(&opt.action, &opt.r#type, &opt.parser));
+      if rename_all.is_some_and(|rename_all| rename_all != "verbatim") {
+        arg = arg.value_name(resolve_option_name(name, rename_all));
+      }
+      let namespace = name.rsplit_once('.').map(|(namespace, _)| namespace);
+      if let Some(namespace) = namespace {
+        arg = arg.help_heading(leak_borrowed_str(namespace));
+      }
+      if opt.r#type.as_deref() != Some("positional") {
+        let long = resolve_long_name(name, opt, rename_all);
+        arg = arg.long(long).short(shorts.get(name.as_str()).copied());
+        if opt.negatable.unwrap_or(false) {
+          let negation_id = leak_str(format!("{}{}", NEGATION_ID_PREFIX, name));
+          negation = Some(
+            clap::Arg::new(negation_id)
+              .long(leak_str(format!("no-{}", long)))
+              .action(clap::ArgAction::SetTrue)
+              .hide(true)
+              .global(opt.global.unwrap_or(false)),
           );
         }
+      }
+      if opt.action.as_deref() == Some("map") {
+        let separator = opt.map_separator.as_deref().unwrap_or("=");
+        arg = arg
+          .value_parser(resolve_map_parser(opt))
+          .value_name(leak_str(format!("KEY{}VALUE", separator)));
+      } else {
         arg = arg.value_parser(resolve_parser(opt.parser.as_deref(), opt.action.as_deref()));
-        apply_opt!(arg, opt, &alias => visible_aliases);
-        apply_opt!(arg, opt, &hidden_alias => aliases);
-        apply_opt!(arg, opt, to_char_vec!(&short_alias) => short_aliases);
-        apply_opt!(arg, opt, to_char_vec!(&hidden_short_alias) => short_aliases);
-        apply_opt!(arg, opt, resolve_value_hint(&value_hint) => value_hint);
-        apply_opt!(arg, opt, &help);
+      }
+      if let Some(choices) = &opt.choices {
+        arg = arg.value_parser(clap::builder::PossibleValuesParser::new(
+          choices.iter().copied(),
+        ));
+      }
+      // The localized help lists the visible aliases instead of clap
+      match is_localized() {
+        true => apply_opt!(arg, opt, &alias => aliases),
+        false => apply_opt!(arg, opt, &alias => visible_aliases),
+      }
+      apply_opt!(arg, opt, &hidden_alias => aliases);
+      apply_opt!(arg, opt, to_char_vec!(&short_alias) => short_aliases);
+      apply_opt!(arg, opt, to_char_vec!(&hidden_short_alias) => short_aliases);
+      apply_opt!(arg, opt, resolve_value_hint(&value_hint) => value_hint);
+      apply_opt!(arg, opt, &help);
+      apply_opt!(arg, opt, long_help);
+      apply_opt!(arg, opt, value_name);
+      apply_opt!(arg, opt, &value_names);
+      apply_opt!(arg, opt, help_heading);
+      apply_opt!(arg, opt, as_usize(display_order) => display_order);
+      apply_opt!(arg, opt, hide_possible_values);
+      apply_opt!(arg, opt, env);
+      apply_opt!(arg, opt, hide_env);
+      apply_opt!(arg, opt, hide_short_help);
+      // Missing options are prompted in the interactive mode instead
+      if !(interactive && is_promptable(opt)) {
         apply_opt!(arg, opt, required);
-        apply_opt!(arg, opt, default => default_value);
-        apply_opt!(arg, opt, default_missing => default_missing_value);
-        apply_opt!(arg, opt, resolve_num_args(num_args) => num_args);
-        apply_opt!(arg, opt, required_equals => require_equals);
-        apply_opt!(arg, opt, hidden => hide);
-        apply_opt!(arg, opt, global);
-        apply_opt!(arg, opt, exclusive);
-        apply_opt!(arg, opt, &conflicts_with => conflicts_with_all);
-        apply_opt!(arg, opt, hide_default_value);
-        arg
-      })
-      .collect::<Vec<clap::Arg>>(),
-  )
+      }
+      apply_opt!(arg, opt, default => default_value);
+      apply_opt!(arg, opt, default_missing => default_missing_value);
+      apply_opt!(arg, opt, resolve_num_args(num_args) => num_args);
+      apply_opt!(arg, opt, required_equals => require_equals);
+      apply_opt!(arg, opt, hidden => hide);
+      apply_opt!(arg, opt, global);
+      apply_opt!(arg, opt, exclusive);
+      if let Some(conflicts_with) = &opt.conflicts_with {
+        arg = arg.conflicts_with_all(conflicts_with.iter().map(|id| resolve_id(id)));
+      }
+      apply_opt!(arg, opt, hide_default_value);
+      if opt.sensitive.unwrap_or(false) {
+        arg = arg.hide_default_value(true);
+      }
+      apply_opt!(arg, opt, last);
+      apply_opt!(arg, opt, trailing_var_arg);
+      apply_opt!(arg, opt, allow_hyphen_values);
+      apply_opt!(arg, opt, allow_negative_numbers);
+      if let Some(value_delimiter) = &opt.value_delimiter {
+        arg = arg.value_delimiter(value_delimiter.chars().next());
+      }
+      if opt.num_args.is_none()
+        && (opt.last.unwrap_or(false) || opt.trailing_var_arg.unwrap_or(false))
+      {
+        arg = arg.num_args(1..);
+      }
+      if is_localized() {
+        let aliases = opt
+          .alias
+          .iter()
+          .flatten()
+          .map(|alias| format!("--{}", alias))
+          .collect();
+        arg = localize_arg(arg, aliases, long_help);
+      }
+      std::iter::once(arg).chain(negation)
+    })
+    .collect::<Vec<clap::Arg>>();
+  // Passthrough arguments must be the last positional arguments
+  args.sort_by_key(|arg| arg.is_last_set() || arg.is_trailing_var_arg_set());
+  // The sections of the headings are ordered by the first arguments in them
+  let (positionals, mut options): (Vec<_>, Vec<_>) =
+    args.into_iter().partition(|arg| arg.is_positional());
+  options.sort_by_key(|arg| arg.get_help_heading().is_some());
+  if is_localized() {
+    clap = clap.next_help_heading(leak_str(message("arguments-heading")));
+  }
+  clap = clap.args(positionals);
+  if is_localized() {
+    clap = clap.next_help_heading(leak_str(message("options-heading")));
+  }
+  clap.args(options)
 }
 
 pub(crate) fn resolve_command(
   mut clap: clap::Command,
   name: String,
   cmd: &Command,
-) -> clap::Command {
-  clap = resolve_command_meta(clap, Some(name), &cmd.meta);
-  clap = resolve_command_options(clap, &cmd.options);
+  inherited: &Inherited,
+) -> napi::Result<clap::Command> {
+  let rename_all = cmd.meta.rename_all.as_deref().or(inherited.rename_all);
+  let shorts = resolve_short_names(&name, cmd, inherited)?;
+  validate_command_options(cmd)?;
+  clap = resolve_command_meta(clap, Some(name), &cmd.meta, inherited)?;
+  clap = resolve_command_options(
+    clap,
+    &cmd.options,
+    rename_all,
+    &shorts,
+    inherited.interactive,
+    has_long_help(cmd, inherited),
+  );
+  clap = resolve_builtin_flags(clap, cmd, inherited)?;
   if let Some(subcommands) = &cmd.subcommands {
+    let propagate_version =
+      inherited.propagate_version || cmd.meta.propagate_version.unwrap_or(false);
+    let mut inherited = Inherited {
+      rename_all,
+      shorts: inherited.shorts.clone(),
+      interactive: inherited.interactive,
+      version_flag: resolve_version_flag(&cmd.meta, inherited)?,
+      propagate_version,
+      version: propagate_version && has_version(&cmd.meta, inherited),
+      styles: inherited
+        .styles
+        .clone()
+        .or_else(|| cmd.meta.is_themed().then(|| clap.get_styles().clone())),
+      long_help: inherited.long_help
+        || cmd
+          .options
+          .values()
+          .any(|opt| opt.global == Some(true) && has_long_option_help(opt)),
+    };
+    for (name, opt) in cmd
+      .options
+      .iter()
+      .filter(|(_, opt)| opt.global == Some(true))
+    {
+      let long = resolve_long_name(name, opt, rename_all);
+      let aliases = opt
+        .short_alias
+        .iter()
+        .chain(opt.hidden_short_alias.iter())
+        .flatten()
+        .filter_map(|alias| alias.chars().next());
+      for short in shorts
+        .get(name.as_str())
+        .copied()
+        .into_iter()
+        .chain(aliases)
+      {
+        inherited.shorts.insert(short, format!("--{}", long));
+      }
+    }
     clap = clap.subcommands(
       subcommands
         .iter()
-        .map(|(name, sub_cmd)| resolve_command(clap::Command::default(), name.clone(), sub_cmd))
-        .collect::<Vec<clap::Command>>(),
+        .map(|(name, sub_cmd)| {
+          resolve_command(clap::Command::default(), name.clone(), sub_cmd, &inherited)
+        })
+        .collect::<napi::Result<Vec<clap::Command>>>()?,
     );
   }
+  Ok(clap)
+}
+
+/// Check the definitions of the options which clap can not check by itself
+fn validate_command_options(cmd: &Command) -> napi::Result<()> {
+  if cmd.meta.multicall.unwrap_or(false) && !cmd.options.is_empty() {
+    return Err(
+      Error::DefinitionError("The multicall command can not have options".to_string()).into(),
+    );
+  }
+  for (name, opt) in &cmd.options {
+    // A namespace can not be an option at the same time, e.g. `db` and `db.host`
+    if let Some(namespace) = name
+      .match_indices('.')
+      .map(|(index, _)| &name[..index])
+      .find(|namespace| cmd.options.contains_key(*namespace))
+    {
+      return Err(
+        Error::DefinitionError(format!(
+          "Option `{}` conflicts with the namespace of option `{}`",
+          namespace, name
+        ))
+        .into(),
+      );
+    }
+    if opt.negatable.unwrap_or(false)
+      && !matches!(
+        resolve_action(&opt.action, &opt.r#type, &opt.parser),
+        clap::ArgAction::SetTrue
+      )
+    {
+      return Err(
+        Error::DefinitionError(format!(
+          "Negatable option `{}` must be a boolean flag",
+          name
+        ))
+        .into(),
+      );
+    }
+    if let Some(policy) = &opt.map_duplicate_key {
+      if !matches!(policy.as_str(), "error" | "last" | "array") {
+        return Err(
+          Error::DefinitionError(format!(
+            "Unsupported duplicate key policy `{}` of option `{}`",
+            policy, name
+          ))
+          .into(),
+        );
+      }
+    }
+    if opt.choices.is_some() {
+      if opt.action.as_deref() == Some("map") {
+        return Err(
+          Error::DefinitionError(format!(
+            "Choices of option `{}` are not supported by `map`",
+            name
+          ))
+          .into(),
+        );
+      }
+      if opt
+        .parser
+        .as_deref()
+        .is_some_and(|parser| parser != "string")
+      {
+        return Err(
+          Error::DefinitionError(format!(
+            "Choices of option `{}` require the string parser",
+            name
+          ))
+          .into(),
+        );
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Display the negatable flags as `--[no-]<long>` in the help of the built
+/// command, the parser keeps matching the long names resolved by the build
+pub(crate) fn display_negatable_flags(mut clap: clap::Command) -> clap::Command {
+  if clap.is_multicall_set() {
+    return clap;
+  }
+  clap.build();
+  let negated = clap
+    .get_arguments()
+    .filter(|arg| arg.get_id().as_str().starts_with(NEGATION_ID_PREFIX))
+    .filter_map(|arg| arg.get_long()?.strip_prefix("no-"))
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+  if !negated.is_empty() {
+    clap = clap.mut_args(|arg| {
+      match arg
+        .get_long()
+        .filter(|long| negated.iter().any(|negated| negated == long))
+      {
+        Some(long) => {
+          let long = leak_str(format!("[no-]{}", long));
+          arg.long(long)
+        }
+        None => arg,
+      }
+    });
+  }
+  for sub_command in clap.get_subcommands_mut() {
+    *sub_command = display_negatable_flags(std::mem::take(sub_command));
+  }
   clap
 }
+
+fn has_unknown_args_allowed(cmd: &Command) -> bool {
+  cmd.meta.allow_unknown_args.unwrap_or(false)
+    || cmd
+      .subcommands
+      .as_ref()
+      .is_some_and(|subcommands| subcommands.values().any(has_unknown_args_allowed))
+}
+
+pub(crate) fn find_subcommand_def<'a>(cmd: &'a Command, name: &str) -> Option<&'a Command> {
+  cmd
+    .subcommands
+    .as_ref()?
+    .iter()
+    .find(|(key, sub_cmd)| sub_cmd.meta.name.as_deref().unwrap_or(key) == name)
+    .map(|(_, sub_cmd)| sub_cmd)
+}
+
+/// Split the raw arguments into the known arguments, which are passed to clap,
+/// and the unknown arguments of the commands with `allow_unknown_args` enabled.
+pub(crate) fn resolve_unknown_args(
+  clap: &clap::Command,
+  cmd: &Command,
+  raw_args: &[String],
+) -> (Vec<String>, Vec<String>) {
+  if !has_unknown_args_allowed(cmd) {
+    return (raw_args.to_vec(), Vec::new());
+  }
+
+  let mut clap = clap.clone();
+  clap.build();
+
+  let mut current = &clap;
+  let mut current_def = Some(cmd);
+  let mut allowed = cmd.meta.allow_unknown_args.unwrap_or(false);
+  let mut positional_index = 0;
+
+  let mut known = Vec::with_capacity(raw_args.len());
+  let mut unknown = Vec::new();
+  let mut iter = raw_args.iter().peekable();
+  if let Some(bin_name) = iter.next() {
+    known.push(bin_name.clone());
+    // The applet of a multicall command is selected by the binary name
+    let applet = std::path::Path::new(bin_name)
+      .file_stem()
+      .and_then(|stem| stem.to_str());
+    if let Some(sub_command) = applet
+      .filter(|_| clap.is_multicall_set())
+      .and_then(|applet| clap.find_subcommand(applet))
+    {
+      current_def = current_def.and_then(|def| find_subcommand_def(def, sub_command.get_name()));
+      allowed = current_def
+        .and_then(|def| def.meta.allow_unknown_args)
+        .unwrap_or(allowed);
+      current = sub_command;
+    }
+  }
+
+  while let Some(token) = iter.next() {
+    if token == "--" {
+      known.push(token.clone());
+      known.extend(iter.cloned());
+      break;
+    }
+
+    // Negative numbers are treated as values instead of options
+    let is_option = token.len() > 1 && token.starts_with('-') && !is_negative_number(token);
+    if is_option && !token.starts_with("--") {
+      let shorts = split_short_cluster(current, token);
+      let last = shorts.last().and_then(|(short, arg)| {
+        arg.filter(|arg| {
+          short.chars().count() == 2 && arg.get_num_args().unwrap_or_default().takes_values()
+        })
+      });
+      if shorts.iter().all(|(_, arg)| arg.is_some()) || !allowed {
+        known.push(token.clone());
+      } else if shorts.iter().all(|(_, arg)| arg.is_none()) {
+        unknown.push(token.clone());
+        take_unknown_value(current, &mut iter, &mut unknown);
+      } else {
+        // The unknown flags in the cluster are split out, e.g. `-vx` into `-v` and `-x`
+        for (short, arg) in &shorts {
+          match arg {
+            Some(_) => known.push(short.clone()),
+            None => unknown.push(short.clone()),
+          }
+        }
+        if shorts.last().is_some_and(|(_, arg)| arg.is_none()) {
+          take_unknown_value(current, &mut iter, &mut unknown);
+        }
+      }
+      if let Some(arg) = last {
+        take_values(arg, &mut iter, &mut known);
+      }
+      continue;
+    }
+    let arg = token
+      .strip_prefix("--")
+      .filter(|_| is_option)
+      .and_then(|long| {
+        let name = long.split_once('=').map_or(long, |(name, _)| name);
+        current.get_arguments().find(|arg| {
+          arg.get_long() == Some(name)
+            || arg
+              .get_all_aliases()
+              .is_some_and(|aliases| aliases.contains(&name))
+        })
+      });
+
+    if let Some(arg) = arg {
+      known.push(token.clone());
+      if !token.contains('=') {
+        take_values(arg, &mut iter, &mut known);
+      }
+    } else if is_option {
+      if !allowed {
+        known.push(token.clone());
+        continue;
+      }
+      unknown.push(token.clone());
+      if !token.contains('=') {
+        take_unknown_value(current, &mut iter, &mut unknown);
+      }
+    } else if let Some(sub_command) = current.find_subcommand(token.as_str()) {
+      known.push(token.clone());
+      current_def = current_def.and_then(|def| find_subcommand_def(def, sub_command.get_name()));
+      allowed = current_def
+        .and_then(|def| def.meta.allow_unknown_args)
+        .unwrap_or(allowed);
+      current = sub_command;
+      positional_index = 0;
+    } else {
+      let positional = current.get_positionals().nth(positional_index);
+      match positional {
+        Some(positional) if positional.is_trailing_var_arg_set() => {
+          known.push(token.clone());
+          known.extend(iter.cloned());
+          break;
+        }
+        Some(positional) => {
+          known.push(token.clone());
+          let multiple = positional
+            .get_num_args()
+            .is_some_and(|range| range.max_values() > 1);
+          if !multiple {
+            positional_index += 1;
+          }
+        }
+        None if allowed => unknown.push(token.clone()),
+        None => known.push(token.clone()),
+      }
+    }
+  }
+
+  (known, unknown)
+}
+
+/// Whether the token is a negative number, e.g. `-1`, `-1.5` and `-1e3`
+///
+/// Only digits, a dot and an exponent are allowed, the same as clap does.
+fn is_negative_number(token: &str) -> bool {
+  let Some(number) = token.strip_prefix('-') else {
+    return false;
+  };
+  let (mantissa, exponent) = match number.split_once(['e', 'E']) {
+    Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+    None => (number, None),
+  };
+  let is_digits = |text: &str| !text.is_empty() && text.bytes().all(|c| c.is_ascii_digit());
+  let is_mantissa = match mantissa.split_once('.') {
+    Some((integer, fraction)) => is_digits(integer) && (fraction.is_empty() || is_digits(fraction)),
+    None => is_digits(mantissa),
+  };
+  is_mantissa && exponent.is_none_or(is_digits)
+}
+
+/// Split a cluster of short flags into the flags with their arguments, the
+/// rest of the cluster after a short option taking values is its value
+fn split_short_cluster<'a>(
+  clap: &'a clap::Command,
+  token: &str,
+) -> Vec<(String, Option<&'a clap::Arg>)> {
+  let mut shorts = Vec::new();
+  for (index, short) in token[1..].char_indices() {
+    let arg = clap.get_arguments().find(|arg| {
+      arg.get_short() == Some(short)
+        || arg
+          .get_all_short_aliases()
+          .is_some_and(|aliases| aliases.contains(&short))
+    });
+    if arg.is_some_and(|arg| arg.get_num_args().unwrap_or_default().takes_values()) {
+      shorts.push((format!("-{}", &token[1 + index..]), arg));
+      break;
+    }
+    shorts.push((format!("-{}", short), arg));
+  }
+  shorts
+}
+
+/// Take the values following a known option without an attached value
+fn take_values<'a>(
+  arg: &clap::Arg,
+  iter: &mut std::iter::Peekable<impl Iterator<Item = &'a String>>,
+  known: &mut Vec<String>,
+) {
+  let num_args = arg.get_num_args().unwrap_or_default();
+  if arg.is_require_equals_set() || !num_args.takes_values() {
+    return;
+  }
+  let mut count = 0;
+  while count < num_args.max_values() {
+    match iter.peek() {
+      Some(value)
+        if arg.is_allow_hyphen_values_set()
+          || (arg.is_allow_negative_numbers_set() && is_negative_number(value))
+          || !value.starts_with('-') =>
+      {
+        known.push(value.to_string());
+        iter.next();
+        count += 1;
+      }
+      _ => break,
+    }
+  }
+}
+
+/// Take the value following an unknown option, which is not a subcommand
+fn take_unknown_value<'a>(
+  clap: &clap::Command,
+  iter: &mut std::iter::Peekable<impl Iterator<Item = &'a String>>,
+  unknown: &mut Vec<String>,
+) {
+  if let Some(value) = iter.peek() {
+    if !value.starts_with('-') && clap.find_subcommand(value.as_str()).is_none() {
+      unknown.push(value.to_string());
+      iter.next();
+    }
+  }
+}
@@ -0,0 +1,517 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{IsTerminal, Write};
+
+use napi::bindgen_prelude::{Either3, ToNapiValue};
+use napi::{CallContext, Env, JsFunction, JsObject, JsUnknown, NapiValue, Ref, ValueType};
+use napi_derive::{js_function, napi};
+
+use crate::command::{run, RunOptions};
+use crate::hooks::{bind, share, then};
+use crate::locale::render_error;
+use crate::types::Command;
+use crate::HashMap;
+
+/// Output stream of the command
+#[derive(Clone, Copy)]
+pub(crate) enum Stream {
+  Stdout,
+  Stderr,
+}
+
+impl Stream {
+  fn is_terminal(self) -> bool {
+    match self {
+      Stream::Stdout => std::io::stdout().is_terminal(),
+      Stream::Stderr => std::io::stderr().is_terminal(),
+    }
+  }
+}
+
+/// Captured output of `runForTest`
+#[derive(Default)]
+struct Capture {
+  stdout: String,
+  stderr: String,
+  answers: VecDeque<Either3<String, bool, Vec<String>>>,
+  stdin: VecDeque<String>,
+  result: Option<Ref<()>>,
+}
+
+thread_local! {
+  static CAPTURE: RefCell<Option<Capture>> = const { RefCell::new(None) };
+}
+
+/// Whether the output is captured by `runForTest`
+pub(crate) fn is_capturing() -> bool {
+  CAPTURE.with_borrow(|capture| capture.is_some())
+}
+
+/// Whether the prompts can be answered, by the user on a TTY or by the
+/// answers of `runForTest`
+pub(crate) fn can_prompt() -> bool {
+  std::io::stdin().is_terminal() || is_capturing()
+}
+
+/// Whether the output written to the stream should be colored
+///
+/// The captured output is only colored if the color is `always`, regardless of
+/// the environment variables which may be set by the test runner.
+pub(crate) fn is_colored(clap: &clap::Command, stream: Stream) -> bool {
+  match clap.get_color() {
+    clap::ColorChoice::Always => true,
+    clap::ColorChoice::Never => false,
+    _ if is_capturing() => false,
+    clap::ColorChoice::Auto => match (env_flag("FORCE_COLOR"), env_flag("NO_COLOR")) {
+      (Some(force), _) => force != "0",
+      (None, Some(_)) => false,
+      (None, None) => stream.is_terminal(),
+    },
+  }
+}
+
+/// Get the environment variable if it is set to a non-empty value
+fn env_flag(name: &str) -> Option<String> {
+  std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Write the output to the stream, or to the buffer if the output is captured
+pub(crate) fn write(stream: Stream, output: &str) -> napi::Result<()> {
+  let captured = CAPTURE.with_borrow_mut(|capture| {
+    let Some(capture) = capture else {
+      return false;
+    };
+    match stream {
+      Stream::Stdout => capture.stdout.push_str(output),
+      Stream::Stderr => capture.stderr.push_str(output),
+    }
+    true
+  });
+  if captured {
+    return Ok(());
+  }
+  match stream {
+    Stream::Stdout => std::io::stdout().write_all(output.as_bytes()),
+    Stream::Stderr => std::io::stderr().write_all(output.as_bytes()),
+  }
+  .map_err(|err| napi::Error::from_reason(err.to_string()))
+}
+
+/// Print a clap error, or the help and version output, the same as clap does
+pub(crate) fn print_clap_error(clap: &clap::Command, error: &clap::Error) -> napi::Result<()> {
+  let stream = match error.use_stderr() {
+    true => Stream::Stderr,
+    false => Stream::Stdout,
+  };
+  let output = render_error(error, clap.get_styles());
+  let output = match is_colored(clap, stream) {
+    true => output.ansi().to_string(),
+    false => output.to_string(),
+  };
+  write(stream, &output)
+}
+
+/// Keep the value returned by the callback as the `result` of `runForTest`
+pub(crate) fn set_result(env: Env, value: &JsUnknown) -> napi::Result<()> {
+  if !is_capturing() {
+    return Ok(());
+  }
+  // References can only be created to objects, so the value is boxed
+  let mut holder = env.create_object()?;
+  holder.set_named_property("value", unsafe { value.cast::<JsUnknown>() })?;
+  let value = env.create_reference(holder)?;
+  let previous = CAPTURE.with_borrow_mut(|capture| {
+    capture
+      .as_mut()
+      .and_then(|capture| capture.result.replace(value))
+  });
+  if let Some(mut previous) = previous {
+    previous.unref(env)?;
+  }
+  Ok(())
+}
+
+fn take_answer(prompt: &str) -> napi::Result<Option<Either3<String, bool, Vec<String>>>> {
+  CAPTURE.with_borrow_mut(|capture| {
+    let Some(capture) = capture else {
+      return Ok(None);
+    };
+    if let Some(answer) = capture.answers.pop_front() {
+      return Ok(Some(answer));
+    }
+    match capture.stdin.pop_front() {
+      Some(line) => Ok(Some(Either3::A(line))),
+      None => Err(napi::Error::from_reason(format!(
+        "No answer for the prompt `{}`",
+        prompt
+      ))),
+    }
+  })
+}
+
+/// Record the rendered prompt and its answer in the captured stdout
+fn render_answer(prompt: &str, answer: &str) -> napi::Result<()> {
+  write(Stream::Stdout, &format!("> {} {}\n", prompt, answer))
+}
+
+/// Take the next line of a REPL if the output is captured
+///
+/// Returns `Some(None)` once the answers and stdin are exhausted, which
+/// ends the REPL.
+pub(crate) fn answer_line(prompt: &str) -> napi::Result<Option<Option<String>>> {
+  let line = CAPTURE.with_borrow_mut(|capture| {
+    let Some(capture) = capture else {
+      return Ok(None);
+    };
+    match capture.answers.pop_front() {
+      Some(Either3::A(line)) => Ok(Some(Some(line))),
+      Some(_) => Err(napi::Error::from_reason(format!(
+        "Invalid answer for the prompt `{}`",
+        prompt
+      ))),
+      None => Ok(Some(capture.stdin.pop_front())),
+    }
+  })?;
+  if let Some(Some(line)) = &line {
+    render_answer(prompt, line)?;
+  }
+  Ok(line)
+}
+
+/// Take the next answer of a text prompt if the output is captured
+pub(crate) fn answer_text(prompt: &str, masked: bool) -> napi::Result<Option<String>> {
+  let Some(answer) = take_answer(prompt)? else {
+    return Ok(None);
+  };
+  let answer = match answer {
+    Either3::A(text) => text,
+    Either3::B(value) => value.to_string(),
+    Either3::C(values) => values.join(","),
+  };
+  match masked {
+    true => render_answer(prompt, "********")?,
+    false => render_answer(prompt, &answer)?,
+  }
+  Ok(Some(answer))
+}
+
+/// Take the next answer of a confirm prompt if the output is captured
+pub(crate) fn answer_bool(prompt: &str) -> napi::Result<Option<bool>> {
+  let Some(answer) = take_answer(prompt)? else {
+    return Ok(None);
+  };
+  let answer = match answer {
+    Either3::B(value) => value,
+    Either3::A(text) => match text.trim().to_lowercase().as_str() {
+      "y" | "yes" | "true" => true,
+      "n" | "no" | "false" => false,
+      _ => {
+        return Err(napi::Error::from_reason(format!(
+          "Invalid answer `{}` for the prompt `{}`",
+          text, prompt
+        )))
+      }
+    },
+    Either3::C(_) => {
+      return Err(napi::Error::from_reason(format!(
+        "Invalid answer for the prompt `{}`",
+        prompt
+      )))
+    }
+  };
+  render_answer(prompt, if answer { "Yes" } else { "No" })?;
+  Ok(Some(answer))
+}
+
+/// Take the next answer of a select or checkbox prompt if the output is captured
+pub(crate) fn answer_choices(
+  prompt: &str,
+  choices: &[String],
+) -> napi::Result<Option<Vec<String>>> {
+  let Some(answer) = take_answer(prompt)? else {
+    return Ok(None);
+  };
+  let answer = match answer {
+    Either3::A(text) => text.split(',').map(|s| s.trim().to_string()).collect(),
+    Either3::B(_) => {
+      return Err(napi::Error::from_reason(format!(
+        "Invalid answer for the prompt `{}`",
+        prompt
+      )))
+    }
+    Either3::C(values) => values,
+  };
+  if let Some(choice) = answer.iter().find(|choice| !choices.contains(choice)) {
+    return Err(napi::Error::from_reason(format!(
+      "Invalid choice `{}` for the prompt `{}`",
+      choice, prompt
+    )));
+  }
+  render_answer(prompt, &answer.join(", "))?;
+  Ok(Some(answer))
+}
+
+/// Options of `runForTest`
+#[napi(object)]
+#[derive(Default)]
+pub struct TestOptions {
+  /// Environment variables set during the run
+  pub env: Option<HashMap<String, String>>,
+  /// Input of the prompts, one line for each prompt after the `answers`
+  /// are exhausted
+  pub stdin: Option<String>,
+  /// Answers of the prompts in order
+  ///
+  /// A string for the text and select prompts, a boolean for the confirm
+  /// prompts and a string array for the checkbox prompts.
+  #[napi(ts_type = "Array<string | boolean | string[]>")]
+  pub answers: Option<Vec<Either3<String, bool, Vec<String>>>>,
+  /// Run in the interactive mode, the missing options are prompted and
+  /// answered by `answers` and `stdin`
+  pub interactive: Option<bool>,
+}
+
+/// Result of `runForTest`
+#[napi(object)]
+pub struct TestResult {
+  /// Captured stdout
+  pub stdout: String,
+  /// Captured stderr
+  pub stderr: String,
+  /// Exit code of the run
+  pub exit_code: i32,
+  /// Value returned by the callback
+  #[napi(ts_type = "any")]
+  pub result: JsUnknown,
+}
+
+/// Replace `write` of the output stream of the process to capture the
+/// output of the callback, returns the original `write`.
+fn patch_stream(env: Env, name: &str, stream: Stream) -> napi::Result<JsFunction> {
+  let process: JsObject = env.get_global()?.get_named_property("process")?;
+  let mut target: JsObject = process.get_named_property(name)?;
+  let original: JsFunction = target.get_named_property("write")?;
+  let write = match stream {
+    Stream::Stdout => env.create_function("write", capture_stdout)?,
+    Stream::Stderr => env.create_function("write", capture_stderr)?,
+  };
+  target.set_named_property("write", write)?;
+  Ok(original)
+}
+
+fn capture_chunk(ctx: &CallContext, stream: Stream) -> napi::Result<JsUnknown> {
+  let chunk = ctx.get::<JsUnknown>(0)?;
+  write(stream, chunk.coerce_to_string()?.into_utf8()?.as_str()?)?;
+  for index in 1..ctx.length {
+    let callback = ctx.get::<JsUnknown>(index)?;
+    if callback.get_type()? == ValueType::Function {
+      unsafe { callback.cast::<JsFunction>() }.call_without_args(None)?;
+    }
+  }
+  Ok(ctx.env.get_boolean(true)?.into_unknown())
+}
+
+#[js_function(3)]
+fn capture_stdout(ctx: CallContext) -> napi::Result<JsUnknown> {
+  capture_chunk(&ctx, Stream::Stdout)
+}
+
+#[js_function(3)]
+fn capture_stderr(ctx: CallContext) -> napi::Result<JsUnknown> {
+  capture_chunk(&ctx, Stream::Stderr)
+}
+
+/// Set the environment variables, returns the previous values
+fn set_env(env: Env, vars: &HashMap<String, String>) -> napi::Result<JsObject> {
+  let process: JsObject = env.get_global()?.get_named_property("process")?;
+  let mut process_env: JsObject = process.get_named_property("env")?;
+  let mut previous = env.create_object()?;
+  for (key, value) in vars {
+    let value_before: JsUnknown = process_env.get_named_property(key)?;
+    previous.set_named_property(key, value_before)?;
+    process_env.set_named_property(key, value)?;
+  }
+  Ok(previous)
+}
+
+fn restore_env(env: Env, previous: &JsObject) -> napi::Result<()> {
+  let process: JsObject = env.get_global()?.get_named_property("process")?;
+  let mut process_env: JsObject = process.get_named_property("env")?;
+  let keys = previous.get_property_names()?;
+  for index in 0..keys.get_array_length()? {
+    let key = keys.get_element::<napi::JsString>(index)?.into_utf8()?;
+    let value: JsUnknown = previous.get_named_property(key.as_str()?)?;
+    match value.get_type()? {
+      ValueType::Undefined => {
+        process_env.delete_named_property(key.as_str()?)?;
+      }
+      _ => process_env.set_named_property(key.as_str()?, value)?,
+    }
+  }
+  Ok(())
+}
+
+/// Take the pending js exception, so that the process can be restored
+/// before the exception is rethrown.
+pub(crate) fn take_exception(env: Env) -> Option<JsUnknown> {
+  let mut pending = false;
+  unsafe { napi::sys::napi_is_exception_pending(env.raw(), &mut pending) };
+  if !pending {
+    return None;
+  }
+  let mut exception = std::ptr::null_mut();
+  unsafe {
+    napi::sys::napi_get_and_clear_last_exception(env.raw(), &mut exception);
+    Some(JsUnknown::from_raw_unchecked(env.raw(), exception))
+  }
+}
+
+/// Stop capturing and restore the process, returns the result of the run
+fn finish(env: Env, restore: &JsObject, exit_code: Option<i32>) -> napi::Result<JsUnknown> {
+  let process: JsObject = env.get_global()?.get_named_property("process")?;
+  for name in ["stdout", "stderr"] {
+    let mut target: JsObject = process.get_named_property(name)?;
+    let write: JsFunction = restore.get_named_property(name)?;
+    target.set_named_property("write", write)?;
+  }
+  restore_env(env, &restore.get_named_property("env")?)?;
+
+  let capture = CAPTURE.take().unwrap_or_default();
+  let result = match capture.result {
+    Some(mut result) => {
+      let holder = env.get_reference_value::<JsObject>(&result)?;
+      result.unref(env)?;
+      holder.get_named_property("value")?
+    }
+    None => env.get_undefined()?.into_unknown(),
+  };
+  let Some(exit_code) = exit_code else {
+    return Ok(env.get_undefined()?.into_unknown());
+  };
+  let result = TestResult {
+    stdout: capture.stdout,
+    stderr: capture.stderr,
+    exit_code,
+    result,
+  };
+  unsafe {
+    let raw = TestResult::to_napi_value(env.raw(), result)?;
+    JsUnknown::from_raw(env.raw(), raw)
+  }
+}
+
+/// Continuation of `runForTest` after the run is fulfilled
+///
+/// Arguments: the restore state and the exit code of the run.
+#[js_function(2)]
+fn finish_run(ctx: CallContext) -> napi::Result<JsUnknown> {
+  let restore = ctx.get::<JsObject>(0)?;
+  let exit_code = ctx.get::<napi::JsNumber>(1)?.get_int32()?;
+  finish(*ctx.env, &restore, Some(exit_code))
+}
+
+/// Continuation of `runForTest` after the run is rejected
+///
+/// Arguments: the restore state and the error of the run.
+#[js_function(2)]
+fn abort_run(ctx: CallContext) -> napi::Result<JsUnknown> {
+  let restore = ctx.get::<JsObject>(0)?;
+  let error = ctx.get::<JsUnknown>(1)?;
+  finish(*ctx.env, &restore, None)?;
+  ctx.env.throw(error)?;
+  Err(napi::Error::new(napi::Status::PendingException, ""))
+}
+
+/// Run command in process for testing
+///
+/// The output of clap, the callback and the hooks is captured instead of
+/// being written to stdout and stderr, progress bars are hidden and the
+/// prompts are answered by `answers` and `stdin` of the options.
+///
+/// **NOTE**: The runs can not be nested or overlapped.
+///
+/// @param cmd Command object
+/// @param args Run with given arguments, starting with the executable and
+/// the script name, e.g. `['node', 'cli.js']`
+/// @param options Test options
+/// @returns {TestResult | Promise<TestResult>}
+#[napi(
+  ts_args_type = "cmd: Command, args: string[], options?: TestOptions",
+  ts_return_type = "TestResult | Promise<TestResult>"
+)]
+pub fn run_for_test(
+  env: Env,
+  cmd: Command,
+  argv: Vec<String>,
+  options: Option<TestOptions>,
+) -> napi::Result<JsUnknown> {
+  if is_capturing() {
+    return Err(napi::Error::from_reason(
+      "Another run for test is in progress",
+    ));
+  }
+  let options = options.unwrap_or_default();
+  let mut restore = env.create_object()?;
+  restore.set_named_property("env", set_env(env, &options.env.unwrap_or_default())?)?;
+  CAPTURE.set(Some(Capture {
+    answers: options.answers.unwrap_or_default().into(),
+    stdin: options
+      .stdin
+      .unwrap_or_default()
+      .lines()
+      .map(|line| line.to_string())
+      .collect(),
+    ..Default::default()
+  }));
+  restore.set_named_property("stdout", patch_stream(env, "stdout", Stream::Stdout)?)?;
+  restore.set_named_property("stderr", patch_stream(env, "stderr", Stream::Stderr)?)?;
+
+  let run_options = RunOptions {
+    interactive: options.interactive,
+  };
+  match run_captured(env, cmd, argv, run_options, &restore) {
+    Ok(value) => Ok(value),
+    Err(err) => {
+      // The process is restored on every failure, even if the run has thrown
+      let exception = take_exception(env);
+      if is_capturing() {
+        finish(env, &restore, None)?;
+      }
+      if let Some(exception) = exception {
+        env.throw(exception)?;
+      }
+      Err(err)
+    }
+  }
+}
+
+/// Run the command while the output is captured, and finish the run once it
+/// is settled
+fn run_captured(
+  env: Env,
+  cmd: Command,
+  argv: Vec<String>,
+  options: RunOptions,
+  restore: &JsObject,
+) -> napi::Result<JsUnknown> {
+  let value = run(env, cmd, Some(argv), Some(options))?;
+  if let Some(exception) = take_exception(env) {
+    env.throw(exception)?;
+    return Err(napi::Error::new(napi::Status::PendingException, ""));
+  }
+  if value.get_type()? == ValueType::Number {
+    let exit_code = unsafe { value.cast::<napi::JsNumber>() }.get_int32()?;
+    return finish(env, restore, Some(exit_code));
+  }
+  let on_fulfilled = bind(
+    env,
+    env.create_function("finish", finish_run)?,
+    vec![share(env, restore)],
+  )?;
+  let on_rejected = bind(
+    env,
+    env.create_function("abort", abort_run)?,
+    vec![share(env, restore)],
+  )?;
+  then(value, on_fulfilled, Some(on_rejected))
+}
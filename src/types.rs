@@ -1,7 +1,12 @@
-use napi::{JsFunction, JsObject};
+use napi::{Either, Env, JsFunction, JsObject, JsUnknown, Ref};
 use napi_derive::napi;
 use thiserror::Error;
 
+use crate::hooks::CommandHooks;
+use crate::locale::localize_usage;
+use crate::resolver::resolve_error_kind;
+use crate::testing::{is_colored, write, Stream};
+use crate::utils::{create_command_error, COMMAND_EXIT_CODE};
 use crate::HashMap;
 
 /// Command context
@@ -14,12 +19,52 @@ pub struct Context {
   /// This is a js object that contains the parsed arguments.
   /// The keys of the object are the names of the arguments and
   /// the values are the parsed values.
-  args: JsObject,
+  args: Ref<()>,
   /// Raw arguments
   ///
   /// The raw arguments parsed by command line or manually given.
   #[napi(ts_type = "string[]")]
   pub raw_args: Vec<String>,
+  /// Unknown arguments
+  ///
+  /// The arguments which are not defined by the command, in their original order.
+  /// Only collected when `allow_unknown_args` is enabled in the command metadata.
+  #[napi(ts_type = "string[]")]
+  pub unknown_args: Vec<String>,
+  /// Matched command path
+  ///
+  /// The names of the matched commands from the main command to the current
+  /// subcommand, e.g. `['mycli', 'remote', 'add']`.
+  #[napi(ts_type = "string[]")]
+  pub command_path: Vec<String>,
+  /// Binary name
+  ///
+  /// The file name of the executed script, as displayed in the usage.
+  pub bin_name: String,
+  /// Metadata of the matched command
+  command: CommandMeta,
+  /// Resolved clap command of the matched command
+  clap: clap::Command,
+  /// Context of the parent command
+  parent: Option<Ref<()>>,
+  /// Shared state
+  ///
+  /// A mutable js object created once per run and shared by the
+  /// providers, lifecycle hooks and the callback.
+  state: Ref<()>,
+  env: Env,
+}
+
+impl Drop for Context {
+  fn drop(&mut self) {
+    // The references keep the objects alive across async hooks,
+    // release them when the context is garbage collected.
+    let _ = self.args.unref(self.env);
+    let _ = self.state.unref(self.env);
+    if let Some(parent) = self.parent.as_mut() {
+      let _ = parent.unref(self.env);
+    }
+  }
 }
 
 #[napi]
@@ -31,18 +76,96 @@ impl Context {
   ///
   /// @param args - Parsed arguments
   /// @param raw_args - Raw arguments
+  /// @param state - Shared state
   #[napi(
     constructor,
-    ts_args_type = "args: Record<string, any>, raw_args: string[]"
+    ts_args_type = "args: Record<string, any>, raw_args: string[], state?: Record<string, any>"
   )]
-  pub fn new(args: JsObject, raw_args: Vec<String>) -> Self {
-    Self { args, raw_args }
+  pub fn new(
+    env: Env,
+    args: JsObject,
+    raw_args: Vec<String>,
+    state: Option<JsObject>,
+  ) -> napi::Result<Self> {
+    let state = match state {
+      Some(state) => state,
+      None => env.create_object()?,
+    };
+    Ok(Self {
+      args: env.create_reference(args)?,
+      raw_args,
+      unknown_args: Vec::new(),
+      command_path: Vec::new(),
+      bin_name: String::new(),
+      command: CommandMeta::default(),
+      clap: clap::Command::default(),
+      parent: None,
+      state: env.create_reference(state)?,
+      env,
+    })
+  }
+
+  pub(crate) fn set_command(&mut self, command: CommandMeta, clap: clap::Command) {
+    self.command = command;
+    self.clap = clap;
+  }
+
+  /// Whether the output written to the stream should be colored
+  pub(crate) fn is_colored(&self, stream: Stream) -> bool {
+    is_colored(&self.clap, stream)
+  }
+
+  /// Styles of the theme for the output written to the stream, or `None` if
+  /// the command is not themed or colors are disabled
+  pub(crate) fn theme_styles(&self, stream: Stream) -> Option<&clap::builder::Styles> {
+    (self.command.is_themed() && self.is_colored(stream)).then(|| self.clap.get_styles())
+  }
+
+  /// Render the styled output as ANSI text if the command is themed and
+  /// colors are enabled, or as plain text otherwise.
+  fn render(&self, output: clap::builder::StyledStr) -> String {
+    if self.theme_styles(Stream::Stdout).is_some() {
+      output.ansi().to_string()
+    } else {
+      output.to_string()
+    }
+  }
+
+  pub(crate) fn set_parent(&mut self, env: Env, parent: Option<JsObject>) -> napi::Result<()> {
+    self.parent = parent
+      .map(|parent| env.create_reference(parent))
+      .transpose()?;
+    Ok(())
   }
 
   /// Get the parsed arguments
   #[napi(getter, ts_return_type = "Record<string, any>")]
-  pub fn args(&self) -> &JsObject {
-    &self.args
+  pub fn args(&self) -> napi::Result<JsObject> {
+    self.env.get_reference_value(&self.args)
+  }
+
+  /// Get the metadata of the matched command
+  #[napi(getter)]
+  pub fn command(&self) -> CommandMeta {
+    self.command.clone()
+  }
+
+  /// Get the context of the parent command
+  ///
+  /// The parent context only contains the arguments of the parent command.
+  #[napi(getter, ts_return_type = "Context | null")]
+  pub fn parent(&self) -> napi::Result<Option<JsObject>> {
+    self
+      .parent
+      .as_ref()
+      .map(|parent| self.env.get_reference_value(parent))
+      .transpose()
+  }
+
+  /// Get the shared state
+  #[napi(getter, ts_return_type = "Record<string, any>")]
+  pub fn state(&self) -> napi::Result<JsObject> {
+    self.env.get_reference_value(&self.state)
   }
 
   /// Get the raw arguments
@@ -51,16 +174,214 @@ impl Context {
     &self.raw_args
   }
 
+  /// Get the unknown arguments
+  #[napi(ts_return_type = "string[]")]
+  pub fn get_unknown_args(&self) -> &Vec<String> {
+    &self.unknown_args
+  }
+
+  /// Get the help message of the matched command
+  #[napi]
+  pub fn help(&self) -> String {
+    self.render_help(None)
+  }
+
+  /// Get the usage of the matched command
+  #[napi]
+  pub fn usage(&self) -> String {
+    let usage = self.clap.clone().render_usage();
+    self.render(localize_usage(usage, self.clap.get_styles()))
+  }
+
+  /// Render the help message of the matched command
+  ///
+  /// @param long - Render the long help message, the same as `--help`
+  #[napi]
+  pub fn render_help(&self, long: Option<bool>) -> String {
+    let mut clap = self.clap.clone();
+    self.render(match long.unwrap_or(false) {
+      true => clap.render_long_help(),
+      false => clap.render_help(),
+    })
+  }
+
+  /// Print the help message of the matched command to stdout
+  ///
+  /// @param long - Print the long help message, the same as `--help`
+  #[napi]
+  pub fn print_help(&self, long: Option<bool>) -> napi::Result<()> {
+    write(Stream::Stdout, &self.render_help(long))
+  }
+
+  /// Raise a clap error of the matched command and end the run
+  ///
+  /// The error is printed to stderr the same way as the parsing errors,
+  /// with the usage and exit code of clap, after the `onError` hooks are called.
+  /// Throws a type error if the kind is not supported.
+  ///
+  /// @param kind - Error kind
+  /// @param message - Error message
+  #[napi(
+    ts_args_type = "kind: 'invalid-value' | 'unknown-argument' | 'invalid-subcommand' | 'no-equals' | 'value-validation' | 'too-many-values' | 'too-few-values' | 'wrong-number-of-values' | 'argument-conflict' | 'missing-required-argument' | 'missing-subcommand' | 'io' | 'format', message: string",
+    ts_return_type = "never"
+  )]
+  pub fn error(&self, env: Env, kind: String, message: String) -> napi::Result<()> {
+    let kind = match resolve_error_kind(&kind) {
+      Ok(kind) => kind,
+      Err(err) => {
+        env.throw_type_error(&err.reason, None)?;
+        return Err(napi::Error::new(napi::Status::PendingException, ""));
+      }
+    };
+    let error = self.clap.clone().error(kind, &message);
+    env.throw(create_command_error(
+      env, &self.clap, &message, error, None,
+    )?)?;
+    Err(napi::Error::new(napi::Status::PendingException, ""))
+  }
+
+  /// Fail the matched command with a custom message and end the run
+  ///
+  /// @param message - Error message
+  /// @param options - Exit code and hint of the failure
+  #[napi(ts_return_type = "never")]
+  pub fn fail(&self, env: Env, message: String, options: Option<FailOptions>) -> napi::Result<()> {
+    let options = options.unwrap_or_default();
+    let mut error = self
+      .clap
+      .clone()
+      .error(clap::error::ErrorKind::Format, &message);
+    if let Some(hint) = options.hint {
+      error.insert(
+        clap::error::ContextKind::Suggested,
+        clap::error::ContextValue::StyledStrs(vec![hint.into()]),
+      );
+    }
+    env.throw(create_command_error(
+      env,
+      &self.clap,
+      &message,
+      error,
+      options.exit_code,
+    )?)?;
+    Err(napi::Error::new(napi::Status::PendingException, ""))
+  }
+
+  /// End the run with the given exit code
+  ///
+  /// The remaining hooks are skipped and `run` returns the exit code.
+  ///
+  /// @param code - Exit code, defaults to `0`
+  #[napi(ts_return_type = "never")]
+  pub fn exit(&self, env: Env, code: Option<i32>) -> napi::Result<()> {
+    let code = code.unwrap_or(0);
+    let mut signal =
+      env.create_error(napi::Error::from_reason(format!("Exit with code {}", code)))?;
+    signal.set_named_property("code", COMMAND_EXIT_CODE)?;
+    signal.set_named_property("exitCode", code)?;
+    env.throw(signal)?;
+    Err(napi::Error::new(napi::Status::PendingException, ""))
+  }
+
   /// Get the argument value by specified key
-  #[napi(ts_return_type = "any")]
-  pub fn get(&self, key: String) -> napi::Result<JsObject> {
-    self.args.get_named_property(&key)
+  ///
+  /// Dotted keys are resolved in the nested namespaces, e.g. `db.host`.
+  #[napi(ts_generic_types = "T = any", ts_return_type = "T")]
+  pub fn get(&self, key: String) -> napi::Result<JsUnknown> {
+    self.get_arg(&key)
+  }
+
+  /// Get the value of specified key in the shared state, which is written
+  /// by `set` or the state providers
+  #[napi(ts_generic_types = "T = any", ts_return_type = "T")]
+  pub fn get_state(&self, key: String) -> napi::Result<JsUnknown> {
+    self.state()?.get_named_property(&key)
+  }
+
+  /// Set the value of specified key in the shared state
+  #[napi(ts_generic_types = "T = any", ts_args_type = "key: string, value: T")]
+  pub fn set(&self, key: String, value: JsUnknown) -> napi::Result<()> {
+    self.state()?.set_named_property(&key, value)
+  }
+
+  fn get_arg(&self, key: &str) -> napi::Result<JsUnknown> {
+    let args = self.args()?;
+    let Some((path, key)) = key
+      .rsplit_once('.')
+      .filter(|_| !args.has_own_property(key).unwrap_or(false))
+    else {
+      return args.get_named_property(key);
+    };
+    let mut namespace: Option<JsObject> = None;
+    for segment in path.split('.') {
+      let parent = namespace.as_ref().unwrap_or(&args);
+      let value: JsUnknown = parent.get_named_property(segment)?;
+      if value.get_type()? != napi::ValueType::Object {
+        return Ok(self.env.get_undefined()?.into_unknown());
+      }
+      namespace = Some(value.coerce_to_object()?);
+    }
+    namespace.unwrap().get_named_property(key)
   }
 }
 
+/// Options of `ctx.fail`
+#[napi(object)]
+#[derive(Default)]
+pub struct FailOptions {
+  /// Exit code of the process, defaults to `2` as the usage errors
+  pub exit_code: Option<i32>,
+  /// Hint displayed as a tip below the error message
+  pub hint: Option<String>,
+}
+
+/// Style of a part of the output
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct ThemeStyle {
+  /// Foreground color
+  ///
+  /// One of the ANSI color names, e.g. `red` or `bright-red`, an index of
+  /// the 256-color palette, e.g. `208`, or a hex RGB color, e.g. `#ff8800`.
+  pub color: Option<String>,
+  /// Background color, in the same format as `color`
+  pub background: Option<String>,
+  /// Text effects
+  #[napi(
+    ts_type = "Array<'bold' | 'dimmed' | 'italic' | 'underline' | 'blink' | 'invert' | 'hidden' | 'strikethrough'>"
+  )]
+  pub effects: Option<Vec<String>>,
+}
+
+/// Theme of the output
+///
+/// The parts which are not specified keep the styles of the preset.
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct Theme {
+  /// Preset which the theme is based on, defaults to `default`
+  #[napi(ts_type = "'default' | 'styled' | 'plain'")]
+  pub preset: Option<String>,
+  /// Section headers, e.g. `Options:`, and the prompt prefixes
+  pub header: Option<ThemeStyle>,
+  /// Usage heading, e.g. `Usage:`
+  pub usage: Option<ThemeStyle>,
+  /// Literal text, e.g. the flags and the subcommands, the highlighted choices
+  /// of the prompts and the progress bars
+  pub literal: Option<ThemeStyle>,
+  /// Placeholders, e.g. `<NAME>`, and the defaults and hints of the prompts
+  pub placeholder: Option<ThemeStyle>,
+  /// Error heading, e.g. `error:`
+  pub error: Option<ThemeStyle>,
+  /// Valid values in the error messages, and the answers of the prompts
+  pub valid: Option<ThemeStyle>,
+  /// Invalid values in the error messages
+  pub invalid: Option<ThemeStyle>,
+}
+
 /// Command metadata
 #[napi(object)]
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CommandMeta {
   /// Command name
   ///
@@ -76,18 +397,181 @@ pub struct CommandMeta {
   /// If not provided, the CLI will not display the version and you can't
   /// call the command with the `--version` flag or `-V` option.
   pub version: Option<String>,
+  /// CLI long version
+  ///
+  /// Displayed instead of `version` when the command is called with the long
+  /// version flag, e.g. `--version`, while `-V` still displays `version`.
+  /// This is useful for multi-line build information, like the commit, the
+  /// build date and the target.
+  ///
+  /// The version flag is enabled if either `version` or `longVersion` is provided.
+  pub long_version: Option<String>,
+  /// Propagate the version to the subcommands
+  ///
+  /// If true, every subcommand accepts the version flag and displays the
+  /// version of the command, unless the subcommand provides its own version.
+  pub propagate_version: Option<bool>,
+  /// Long name of the version flag
+  ///
+  /// Inherited by the subcommands. Defaults to `version`.
+  pub version_flag: Option<String>,
+  /// Short name of the version flag
+  ///
+  /// Pass `false` to disable the short flag. Only the first character is used.
+  /// Inherited by the subcommands. Defaults to `V`.
+  #[napi(ts_type = "string | false")]
+  pub version_short: Option<Either<String, bool>>,
   /// Command description
   ///
   /// Command description will be displayed in the help output.
   pub about: Option<String>,
+  /// Long command description
+  ///
+  /// Displayed instead of `about` in the long help output, e.g. `--help`.
+  pub long_about: Option<String>,
+  /// Command author
+  ///
+  /// Only displayed with the `{author}` placeholder of `help_template`.
+  pub author: Option<String>,
+  /// Text displayed before the help output
+  pub before_help: Option<String>,
+  /// Text displayed before the long help output, defaults to `before_help`
+  pub before_long_help: Option<String>,
+  /// Text displayed after the help output
+  pub after_help: Option<String>,
+  /// Text displayed after the long help output, defaults to `after_help`
+  pub after_long_help: Option<String>,
+  /// Usage examples
+  ///
+  /// Displayed as the `Examples` section after the options, one example
+  /// per line, e.g. `["mycli deploy --env prod", "mycli deploy --dry-run"]`.
+  pub examples: Option<Vec<String>>,
+  /// Custom help template
+  ///
+  /// The placeholders are `{name}`, `{version}`, `{author}`, `{about}`,
+  /// `{usage-heading}`, `{usage}`, `{all-args}`, `{options}`, `{positionals}`,
+  /// `{subcommands}`, `{tab}`, `{before-help}` and `{after-help}`, e.g.
+  /// `"{about}\n\n{usage-heading} {usage}\n\n{all-args}{after-help}"`.
+  pub help_template: Option<String>,
+  /// Custom usage string, which overrides the generated usage
+  pub usage: Option<String>,
+  /// Display the help of the options on the line after their flags
+  pub next_line_help: Option<bool>,
   /// Enable styled mode
   ///
-  /// Determines whether the CLI output should be displayed in the styled format.
+  /// Determines whether the CLI output should be displayed in the styled format,
+  /// the same as the `styled` theme. Ignored if `theme` is specified.
   pub styled: Option<bool>,
+  /// Theme of the help, error, prompt and progress bar output
+  ///
+  /// Either the name of a preset or the styles of each part of the output:
+  ///
+  /// - `default`: Bold and underlined headers, the default of clap
+  /// - `styled`: Green headers and cyan flags
+  /// - `plain`: No styles
+  ///
+  /// A subcommand can only specify its own theme if none of its parents do,
+  /// otherwise the theme is inherited.
+  #[napi(ts_type = "'default' | 'styled' | 'plain' | Theme")]
+  pub theme: Option<Either<String, Theme>>,
+  /// When to use colors in the output
+  ///
+  /// - `auto`: Use colors if the output is a TTY, unless `NO_COLOR` is set to
+  ///   a non-empty value, or if `FORCE_COLOR` is set to a value other than `0`
+  /// - `always`: Always use colors
+  /// - `never`: Never use colors
+  ///
+  /// Inherited by the subcommands. Defaults to `auto`.
+  #[napi(ts_type = "'auto' | 'always' | 'never'")]
+  pub color: Option<String>,
+  /// Locale of the built-in messages
+  ///
+  /// Translates the help headings, the descriptions of `--help`, `--version`
+  /// and the `help` subcommand, the error messages of clap and the default
+  /// help messages of the prompts. Only the locale of the main command is used,
+  /// and the prompts called after the run are not translated.
+  ///
+  /// Defaults to the messages of clap and inquire, which are in English.
+  #[napi(ts_type = "'en' | 'zh-CN'")]
+  pub locale: Option<String>,
+  /// Overrides of the built-in messages
+  ///
+  /// Keyed by the message ids, see `getMessages` for the ids and the messages
+  /// of the locales, e.g. `{ 'usage-heading': 'USAGE' }`. The placeholders
+  /// of the messages, e.g. `{arg}`, are replaced by the values. Only the
+  /// messages of the main command are used.
+  #[napi(ts_type = "Record<string, string>")]
+  pub messages: Option<HashMap<String, String>>,
   /// Subcommand required
   ///
   /// If true, the command will fail if no subcommand is provided.
   pub subcommand_required: Option<bool>,
+  /// Multicall mode
+  ///
+  /// If true, the executable name (without the extension) selects the
+  /// subcommand to run, like busybox. For example, with the `mycli-fmt` bin
+  /// linked to the same script, `mycli-fmt --check` runs the `mycli-fmt`
+  /// subcommand with `--check`, and its help is displayed as `mycli-fmt`.
+  ///
+  /// To run the applets from the canonical name as well, e.g. `mycli fmt`,
+  /// add a subcommand with the canonical name which contains the applets.
+  ///
+  /// The multicall command can not have options, a definition error is thrown
+  /// if it has.
+  pub multicall: Option<bool>,
+  /// Allow unknown arguments
+  ///
+  /// If true, the options which are not defined by the command (and their values)
+  /// will be collected into `ctx.unknownArgs` in their original order instead of
+  /// failing, and the known options will be parsed as usual. This is useful for
+  /// wrapper CLIs that forward the unknown options to the wrapped tool.
+  ///
+  /// An unknown option takes the following argument as its value unless it starts
+  /// with `-` or is a subcommand name, so prefer `--option=value` if it is a flag
+  /// followed by a positional argument.
+  ///
+  /// Subcommands inherit this setting unless it is explicitly disabled.
+  pub allow_unknown_args: Option<bool>,
+  /// Naming policy for the option flags
+  ///
+  /// - `verbatim`: Use the option keys as the long flags, e.g. `--dryRun`
+  /// - `kebab-case`: Derive kebab-case long flags from the option keys,
+  ///   e.g. `--dry-run` for `dryRun`
+  ///
+  /// The keys of `ctx.args` are always the option keys. `conflicts_with` accepts
+  /// either the option keys or the renamed names. Explicit `long` names are not
+  /// renamed.
+  ///
+  /// Subcommands inherit this setting unless it is explicitly specified.
+  ///
+  /// Defaults to `verbatim`.
+  #[napi(ts_type = "'verbatim' | 'kebab-case'")]
+  pub rename_all: Option<String>,
+  /// Exit codes of the errors
+  ///
+  /// Maps the errors thrown by the hooks or the callback to the exit code of
+  /// `run`, keyed by the `code` of the error or the name of its class, e.g.
+  /// `{ ENOENT: 3, ValidationError: 4 }`. The mapped errors are printed to
+  /// stderr instead of being rethrown. Inherited by the subcommands.
+  #[napi(ts_type = "Record<string, number>")]
+  pub exit_codes: Option<HashMap<String, i32>>,
+  /// Interactive menu of the subcommands
+  ///
+  /// If true and the command is run without a subcommand while it has no
+  /// callback, a select of the subcommands (with their `about`) is displayed
+  /// instead of failing, until a command with a callback is picked. The
+  /// picked command is run as if it had been typed, and its missing required
+  /// options are prompted. Only available when stdin is a TTY.
+  ///
+  /// Inherited by the subcommands.
+  pub interactive_menu: Option<bool>,
+}
+
+impl CommandMeta {
+  /// Whether the output is styled by a theme
+  pub(crate) fn is_themed(&self) -> bool {
+    self.theme.is_some() || self.styled.unwrap_or(false)
+  }
 }
 
 #[napi(object)]
@@ -117,21 +601,29 @@ pub struct CommandOption {
   /// - `count`: Count how many times a flag occurs
   /// - `store`: Store the value as a boolean flag
   /// - `store_false`: Store the value as a boolean flag with opposite meaning
+  /// - `map`: Collect `KEY=VALUE` pairs into an object without prototype
   ///
   /// Defaults to `set` if not specified.
-  #[napi(ts_type = "'set' | 'append' | 'count' | 'store' | 'store_false'")]
+  #[napi(ts_type = "'set' | 'append' | 'count' | 'store' | 'store_false' | 'map'")]
   pub action: Option<String>,
   /// Short option name
   ///
   /// This is a single character that can be used to represent the option
   /// in the command line. For example, `-v` for the `--verbose` option.
-  /// Panics if the length of the string is empty. If the size of string
+  /// Fails if the length of the string is empty. If the size of string
   /// is greater than 1, the first character will be used as the short option.
+  /// Pass `false` to disable the short option name.
+  ///
+  /// Explicit short option names must not collide with each other, the built-in
+  /// `-h` and `-V` flags or the global options of the parent commands.
   ///
   /// This option will be ignored if option `type` is not `option`.
   ///
-  /// Defaults to the first character of the long option name.
-  pub short: Option<String>,
+  /// Defaults to the first character of the long option name if it is not taken
+  /// by other options (checked in alphabetical order of the option keys), or no
+  /// short option name for namespaced options.
+  #[napi(ts_type = "string | boolean")]
+  pub short: Option<Either<String, bool>>,
   /// Long option name
   ///
   /// This is the name of the option that will be used to represent the option,
@@ -169,6 +661,28 @@ pub struct CommandOption {
   pub value_hint: Option<String>,
   /// Option description
   pub help: Option<&'static str>,
+  /// Long option description
+  ///
+  /// Displayed instead of `help` in the long help output, e.g. `--help`.
+  pub long_help: Option<&'static str>,
+  /// Placeholder of the value in the help output, e.g. `FILE` for `--out <FILE>`
+  pub value_name: Option<&'static str>,
+  /// Placeholders of the values in the help output, one for each value,
+  /// e.g. `["KEY", "VALUE"]` for `--set <KEY> <VALUE>`
+  pub value_names: Option<Vec<&'static str>>,
+  /// Heading of the section which the option is grouped into in the help output
+  pub help_heading: Option<&'static str>,
+  /// Order of the option in the help output, the lower the earlier
+  pub display_order: Option<u32>,
+  /// Hide the possible values of `choices` in the help output
+  pub hide_possible_values: Option<bool>,
+  /// Environment variable which the value is read from when the option is not given
+  pub env: Option<&'static str>,
+  /// Hide the environment variable and its value in the help output
+  pub hide_env: Option<bool>,
+  /// Hide the option in the short help output, e.g. `-h`, but keep it in the
+  /// long help output, e.g. `--help`
+  pub hide_short_help: Option<bool>,
   /// Required argument
   ///
   /// If true, the argument is required and the command will fail without it.
@@ -214,6 +728,85 @@ pub struct CommandOption {
   /// This is useful when default behavior of an arg is explained elsewhere
   /// in the help text.
   pub hide_default_value: Option<bool>,
+  /// Last positional argument
+  ///
+  /// This argument can only be provided after `--`, and all the values
+  /// after `--` will be collected verbatim. For example, `exec -- npm test --watch`.
+  ///
+  /// Defaults `num_args` to `1..` if not specified.
+  pub last: Option<bool>,
+  /// Trailing variable argument
+  ///
+  /// This is a "var arg" and everything that follows should be captured by it,
+  /// as if the user had used a `--`. Only valid for the last positional argument.
+  ///
+  /// Defaults `num_args` to `1..` if not specified.
+  pub trailing_var_arg: Option<bool>,
+  /// Allow values which start with a leading hyphen (`-`)
+  ///
+  /// To start the trailing var arg on unknown flags, set this option along
+  /// with `trailing_var_arg`.
+  pub allow_hyphen_values: Option<bool>,
+  /// Allow negative numbers to pass as values
+  ///
+  /// This is similar to `allow_hyphen_values` except that it only allows numbers,
+  /// all other undefined leading hyphens will fail to parse.
+  pub allow_negative_numbers: Option<bool>,
+  /// Generate a paired `--no-<long>` flag for the boolean flag
+  ///
+  /// The last one of the pair given in the command line wins, and a given
+  /// negation beats the value of `env` and `default`. This is useful to turn
+  /// off a flag enabled by default, e.g. `--color` and `--no-color`. Displayed as `--[no-]color` in help output.
+  ///
+  /// Throws a definition error if the option is not a boolean flag.
+  pub negatable: Option<bool>,
+  /// Separator between the key and the value of `map` options
+  ///
+  /// For example, `:` for `--header 'X-A: b'`. Whitespaces around the key
+  /// and the value are trimmed, and the key must not be empty.
+  ///
+  /// Defaults to `=`.
+  pub map_separator: Option<String>,
+  /// How to handle duplicate keys of `map` options
+  ///
+  /// - `error`: Fail the parsing with a clap error if a key is given more than once
+  /// - `last`: The last value wins
+  /// - `array`: Collect all the values of each key into an array
+  ///
+  /// Defaults to `last`.
+  #[napi(ts_type = "'error' | 'last' | 'array'")]
+  pub map_duplicate_key: Option<String>,
+  /// Split the values of the argument by the given delimiter
+  ///
+  /// For example, `,` makes `--targets a,b,c` behave like
+  /// `--targets a --targets b --targets c`, and each value is parsed
+  /// by the specified `parser`. Only the first character is used.
+  pub value_delimiter: Option<String>,
+  /// Possible values of the argument
+  ///
+  /// The values not in the choices are rejected, and the choices are displayed
+  /// in the help message. Only available for the `string` parser, and not
+  /// for the `map` action.
+  pub choices: Option<Vec<&'static str>>,
+  /// Sensitive argument
+  ///
+  /// The default value is hidden in the help message, and the argument is
+  /// prompted as a password in the interactive mode.
+  pub sensitive: Option<bool>,
+  /// Prompt for the argument in the interactive mode
+  ///
+  /// If the argument is not given in the command line, it is prompted with
+  /// the given message, or with the `help` (or the option name) if `true`.
+  /// The required arguments are prompted by default, pass `false` to fail
+  /// without them as usual.
+  ///
+  /// Booleans are prompted with a confirm, `choices` with a select (or a
+  /// checkbox for multiple values), `sensitive` arguments with a password,
+  /// and others with a text input, where multiple values are split with the
+  /// shell quoting rules. `count` and `map` arguments are never prompted, nor
+  /// the arguments conflicting with the given or prompted ones.
+  #[napi(ts_type = "string | boolean")]
+  pub prompt: Option<Either<String, bool>>,
 }
 
 /// Command definition
@@ -223,9 +816,37 @@ pub struct CommandOption {
 #[napi(object)]
 pub struct Command {
   pub meta: CommandMeta,
+  /// Command options
+  ///
+  /// Dotted option names are namespaced, e.g. `db.host` and `db.port` are
+  /// parsed from `--db.host` and `--db.port` into `ctx.args.db.host` and
+  /// `ctx.args.db.port`, the same as a config file keyed by `db`. Namespaced
+  /// options are grouped under a heading per namespace in help output, and a
+  /// namespace can not be an option itself, e.g. `db` with `db.host`.
   pub options: HashMap<String, CommandOption>,
-  #[napi(ts_type = "(ctx: Context) => void")]
+  #[napi(ts_type = "(ctx: Context) => void | number | Promise<void | number>")]
   pub callback: Option<JsFunction>,
+  /// Lifecycle hooks
+  ///
+  /// Hooks of parent commands also apply to their subcommands.
+  pub hooks: Option<CommandHooks>,
+  /// State provider
+  ///
+  /// Called before the `preRun` hook of this command, the properties of the
+  /// returned object are assigned to `ctx.state`, e.g. a database connection
+  /// opened by the main command for its subcommands.
+  #[napi(
+    ts_type = "(ctx: Context) => Record<string, any> | void | Promise<Record<string, any> | void>"
+  )]
+  pub provide: Option<JsFunction>,
+  /// Version callback
+  ///
+  /// Called instead of displaying the static version when the version flag is
+  /// given, the returned string is printed to stdout, e.g. the versions of the
+  /// runtime or the plugins loaded at runtime. The callback of the deepest
+  /// matched command is used. The command must still provide a `version`.
+  #[napi(ts_type = "() => string | void | Promise<string | void>")]
+  pub version_callback: Option<JsFunction>,
   pub subcommands: Option<HashMap<String, Command>>,
 }
 
@@ -238,6 +859,8 @@ pub enum Error {
   IndicatifTemplateError(#[from] indicatif::style::TemplateError),
   #[error("Inquire error: {0}")]
   InquireError(#[from] inquire::InquireError),
+  #[error("Definition error: {0}")]
+  DefinitionError(String),
 }
 
 impl From<Error> for napi::Error {
@@ -1,23 +1,137 @@
-use napi::{Env, JsNull, JsObject};
+use std::cell::RefCell;
 
-use crate::types::{Command, Context};
+use clap::error::{ContextKind, ContextValue};
+use clap::parser::ValueSource;
+use napi::{Env, JsFunction, JsObject, JsUnknown};
+use rustc_hash::FxHashSet;
+
+use crate::hooks::{exit_with, resolve_provider, run_with_hooks, share, CommandHooks};
+use crate::inquire::{apply_prompt_theme, is_promptable, prompt_option};
+use crate::locale::render_error;
+use crate::testing::{is_colored, print_clap_error, Stream};
+use crate::types::{Command, CommandMeta, CommandOption, Context};
 use crate::HashMap;
 
 const ISSUE_LINK: &str = "https://github.com/noctisynth/archons/issues";
 
+/// Error code of the errors raised by `ctx.error` and `ctx.fail`
+pub(crate) const COMMAND_ERROR_CODE: &str = "E_COMMAND_ERROR";
+
+/// Error code of the signal raised by `ctx.exit`
+pub(crate) const COMMAND_EXIT_CODE: &str = "E_COMMAND_EXIT";
+
+/// Prefix of the argument ids of the generated `--no-<long>` flags
+pub(crate) const NEGATION_ID_PREFIX: &str = "!";
+
+thread_local! {
+  /// Strings leaked for clap, the commands are resolved again for each run
+  /// and each line of a REPL, so the same strings are only leaked once
+  static LEAKED: RefCell<FxHashSet<&'static str>> = RefCell::default();
+}
+
 #[inline]
 pub(crate) fn leak_str<'a>(s: String) -> &'a str {
-  s.leak()
+  LEAKED.with_borrow_mut(|leaked| match leaked.get(s.as_str()) {
+    Some(leaked) => *leaked,
+    None => {
+      let s: &'static str = s.leak();
+      leaked.insert(s);
+      s
+    }
+  })
 }
 
+/// Leak a copy of the borrowed string, the resolved clap command is kept
+/// by the contexts and may outlive the command definition.
 #[inline]
 pub(crate) fn leak_borrowed_str<'a>(s: &str) -> &'a str {
-  unsafe { std::mem::transmute(s) }
+  match LEAKED.with_borrow(|leaked| leaked.get(s).copied()) {
+    Some(leaked) => leaked,
+    None => leak_str(s.to_string()),
+  }
 }
 
-#[inline]
-pub(crate) fn leak_borrowed_str_or_default<'a>(s: Option<&String>, default: &str) -> &'a str {
-  s.map_or_else(|| leak_borrowed_str(default), |s| leak_borrowed_str(s))
+/// Convert a camelCase, PascalCase or snake_case name into kebab-case,
+/// e.g. `dryRun` into `dry-run` and `HTTPServer` into `http-server`.
+pub(crate) fn to_kebab_case(name: &str) -> String {
+  let chars = name.chars().collect::<Vec<char>>();
+  let mut kebab = String::with_capacity(name.len() + 4);
+  for (i, &c) in chars.iter().enumerate() {
+    if c == '_' || c == '-' {
+      if !kebab.is_empty() && !kebab.ends_with('-') {
+        kebab.push('-');
+      }
+      continue;
+    }
+    if c.is_uppercase() && i > 0 {
+      let prev = chars[i - 1];
+      let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+      if (prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower))
+        && !kebab.ends_with('-')
+      {
+        kebab.push('-');
+      }
+    }
+    kebab.extend(c.to_lowercase());
+  }
+  kebab
+}
+
+/// Split a command line into words with the POSIX shell quoting rules,
+/// e.g. `add "hello world" it\'s` into `["add", "hello world", "it's"]`.
+///
+/// Single quotes keep the text as is, while a backslash escapes the next
+/// character outside quotes and `"`, `\`, `$` and `` ` `` inside double quotes.
+pub(crate) fn split_words(line: &str) -> napi::Result<Vec<String>> {
+  let mut words = Vec::new();
+  let mut word: Option<String> = None;
+  let mut chars = line.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      c if c.is_whitespace() => words.extend(word.take()),
+      '\'' => {
+        let word = word.get_or_insert_with(String::new);
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(c) => word.push(c),
+            None => return Err(napi::Error::from_reason("unterminated single quote")),
+          }
+        }
+      }
+      '"' => {
+        let word = word.get_or_insert_with(String::new);
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+              Some('\n') => {}
+              Some(c) => {
+                word.push('\\');
+                word.push(c);
+              }
+              None => return Err(napi::Error::from_reason("unterminated double quote")),
+            },
+            Some(c) => word.push(c),
+            None => return Err(napi::Error::from_reason("unterminated double quote")),
+          }
+        }
+      }
+      '\\' => match chars.next() {
+        Some('\n') => {}
+        Some(c) => word.get_or_insert_with(String::new).push(c),
+        None => {
+          return Err(napi::Error::from_reason(
+            "unexpected end of line after `\\`",
+          ))
+        }
+      },
+      c => word.get_or_insert_with(String::new).push(c),
+    }
+  }
+  words.extend(word);
+  Ok(words)
 }
 
 #[inline(always)]
@@ -54,17 +168,190 @@ pub fn wrap_bool_parser<'a>(
   Box::leak(parser)
 }
 
+/// Create a js error from a clap error of the matched command
+///
+/// The error keeps the clap formatted output, which is printed to stderr when
+/// the error ends the run, and the exit code of the process.
+pub(crate) fn create_command_error(
+  env: Env,
+  clap: &clap::Command,
+  message: &str,
+  mut error: clap::Error,
+  exit_code: Option<i32>,
+) -> napi::Result<JsObject> {
+  // The usage is formatted into the custom messages by clap, which is kept
+  // in the context as well so that it can be found when rendering the error
+  if error.get(ContextKind::Usage).is_none() {
+    let usage = clap.clone().render_usage();
+    error.insert(ContextKind::Usage, ContextValue::StyledStr(usage));
+  }
+  let output = render_error(&error, clap.get_styles());
+  let output = match is_colored(clap, Stream::Stderr) {
+    true => output.ansi().to_string(),
+    false => output.to_string(),
+  };
+  let mut js_error = env.create_error(napi::Error::from_reason(message))?;
+  js_error.set_named_property("code", COMMAND_ERROR_CODE)?;
+  js_error.set_named_property("exitCode", exit_code.unwrap_or(error.exit_code()))?;
+  js_error.set_named_property("output", output)?;
+  Ok(js_error)
+}
+
+/// Whether the argument may hold more than one value, e.g. `append` options,
+/// delimited values, passthrough arguments or options with a `num_args` range.
+#[inline]
+fn is_multiple_values(arg: &clap::Arg) -> bool {
+  matches!(arg.get_action(), clap::ArgAction::Append)
+    || arg.get_value_delimiter().is_some()
+    || arg
+      .get_num_args()
+      .is_some_and(|range| range.max_values() > 1)
+}
+
+fn set_values<T>(
+  target: &mut JsObject,
+  key: &str,
+  id: &clap::Id,
+  matches: &clap::ArgMatches,
+  multiple: bool,
+) -> napi::Result<()>
+where
+  T: Clone + Send + Sync + napi::bindgen_prelude::ToNapiValue + 'static,
+{
+  if multiple {
+    target.set(
+      key,
+      matches
+        .get_many::<T>(id.as_str())
+        .unwrap_or_default()
+        .cloned()
+        .collect::<Vec<_>>(),
+    )?;
+  } else if let Some(value) = matches.get_one::<T>(id.as_str()) {
+    target.set(key, value.clone())?;
+  }
+  Ok(())
+}
+
+fn resolve_map_value(env: Env, parser: &str, value: &str) -> napi::Result<JsUnknown> {
+  Ok(match parser {
+    "number" => env.create_int64(value.parse().unwrap())?.into_unknown(),
+    "boolean" => env.get_boolean(value.parse().unwrap())?.into_unknown(),
+    _ => env.create_string(value)?.into_unknown(),
+  })
+}
+
+fn merge_map_values(
+  env: Env,
+  id: &clap::Id,
+  option: &CommandOption,
+  matches: &clap::ArgMatches,
+) -> napi::Result<JsObject> {
+  let parser = option.parser.as_deref().unwrap_or("string");
+  let mut object = create_null_object(env)?;
+  let mut entries: Vec<(&str, Vec<JsUnknown>)> = Vec::new();
+  for (key, value) in matches
+    .get_many::<(String, String)>(id.as_str())
+    .unwrap_or_default()
+  {
+    let value = resolve_map_value(env, parser, value)?;
+    // The duplicate keys of the `error` policy are rejected while parsing
+    match option.map_duplicate_key.as_deref() {
+      Some("array") => match entries.iter_mut().find(|(k, _)| k == key) {
+        Some((_, values)) => values.push(value),
+        None => entries.push((key, vec![value])),
+      },
+      _ => object.set_named_property(key, value)?,
+    }
+  }
+  for (key, values) in entries {
+    object.set_named_property(key, values)?;
+  }
+  Ok(object)
+}
+
+/// Find the first key given more than once to the `map` options with the
+/// `error` duplicate key policy, reported as a clap error of the command
+fn find_duplicate_map_key(
+  built: &clap::Command,
+  args: &[&clap::Arg],
+  options: &HashMap<String, CommandOption>,
+  matches: &clap::ArgMatches,
+) -> Option<clap::Error> {
+  args
+    .iter()
+    .filter(|arg| {
+      options.get(arg.get_id().as_str()).is_some_and(|opt| {
+        opt.action.as_deref() == Some("map") && opt.map_duplicate_key.as_deref() == Some("error")
+      })
+    })
+    .find_map(|arg| {
+      let mut keys = FxHashSet::default();
+      let (key, _) = matches
+        .get_many::<(String, String)>(arg.get_id().as_str())
+        .unwrap_or_default()
+        .find(|(key, _)| !keys.insert(key.as_str()))?;
+      Some(built.clone().error(
+        clap::error::ErrorKind::ValueValidation,
+        format!("duplicate key '{}' for '{}'", key, arg),
+      ))
+    })
+}
+
+/// Create an object without prototype, so that the keys given by users such
+/// as `__proto__` are kept as its own properties
+fn create_null_object(env: Env) -> napi::Result<JsObject> {
+  let object = env
+    .get_global()?
+    .get_named_property::<JsUnknown>("Object")?
+    .coerce_to_object()?;
+  let create: JsFunction = object.get_named_property("create")?;
+  create
+    .call(Some(&object), &[env.get_null()?])?
+    .coerce_to_object()
+}
+
+/// Resolve the namespace object of a dotted option name, e.g. `db.host`
+/// is written into `parsed_args.db` with key `host`.
+///
+/// Returns `None` as the namespace if the option is not namespaced.
+fn resolve_namespace<'a>(
+  env: Env,
+  parsed_args: &mut JsObject,
+  id: &'a str,
+) -> napi::Result<(Option<JsObject>, &'a str)> {
+  let Some((path, key)) = id.rsplit_once('.') else {
+    return Ok((None, id));
+  };
+  let mut namespace: Option<JsObject> = None;
+  for segment in path.split('.') {
+    let parent = match namespace.as_mut() {
+      Some(namespace) => namespace,
+      None => &mut *parsed_args,
+    };
+    if !parent.has_own_property(segment)? {
+      parent.set_named_property(segment, env.create_object()?)?;
+    }
+    let child: JsObject = parent.get_named_property(segment)?;
+    namespace = Some(child);
+  }
+  Ok((namespace, key))
+}
+
 pub(crate) fn merge_args_matches(
+  env: Env,
   parsed_args: &mut JsObject,
   args: &[&clap::Arg],
-  options: &HashMap<String, &'static str>,
+  options: &HashMap<String, CommandOption>,
   matches: &clap::ArgMatches,
 ) -> napi::Result<()> {
   for id in matches.ids() {
-    let action = args
+    if id.as_str().starts_with(NEGATION_ID_PREFIX) {
+      continue;
+    }
+    let arg = args
       .iter()
       .find(|arg| arg.get_id() == id)
-      .map(|arg| arg.get_action())
       .unwrap_or_else(|| {
         panic!(
           "Argument not found when merging matches, this is likely a internal bug.\n
@@ -72,71 +359,202 @@ pub(crate) fn merge_args_matches(
           ISSUE_LINK
         )
       });
-    let option: &str = options.get(id.as_str()).unwrap();
+    let action = arg.get_action();
+    let option = options.get(id.as_str()).unwrap();
+    let parser = option.parser.as_deref().unwrap_or("string");
+    let (mut namespace, key) = resolve_namespace(env, parsed_args, id.as_str())?;
+    let target = match namespace.as_mut() {
+      Some(namespace) => namespace,
+      None => &mut *parsed_args,
+    };
     match action {
-      clap::ArgAction::Set => match option {
-        "string" => {
-          parsed_args.set(id, matches.get_one::<String>(id.as_str()).unwrap())?;
-        }
-        "number" => {
-          parsed_args.set(id, *matches.get_one::<i64>(id.as_str()).unwrap())?;
+      clap::ArgAction::Append if option.action.as_deref() == Some("map") => {
+        target.set(key, merge_map_values(env, id, option, matches)?)?;
+      }
+      clap::ArgAction::Set | clap::ArgAction::Append => {
+        let multiple = is_multiple_values(arg);
+        match parser {
+          "string" => set_values::<String>(target, key, id, matches, multiple)?,
+          "number" => set_values::<i64>(target, key, id, matches, multiple)?,
+          "boolean" => set_values::<bool>(target, key, id, matches, multiple)?,
+          _ => panic!("Invalid option type: {}", parser),
         }
-        _ => panic!("Invalid option type: {}", option),
-      },
+      }
       clap::ArgAction::SetTrue | clap::ArgAction::SetFalse => {
-        parsed_args.set(id, matches.get_flag(id.as_str()))?;
+        let negation_id = format!("{}{}", NEGATION_ID_PREFIX, id);
+        let negated = args.iter().any(|arg| arg.get_id() == negation_id.as_str())
+          && is_last_occurrence(matches, &negation_id, id.as_str());
+        target.set(key, !negated && matches.get_flag(id.as_str()))?;
       }
       clap::ArgAction::Count => {
-        parsed_args.set(id, matches.get_count(id.as_str()))?;
+        target.set(key, matches.get_count(id.as_str()))?;
       }
-      clap::ArgAction::Append => match option {
-        "string" => {
-          parsed_args.set(
-            id,
-            matches
-              .get_many::<String>(id.as_str())
-              .unwrap_or_default()
-              .map(|v| v.as_str())
-              .collect::<Vec<_>>(),
-          )?;
-        }
-        "number" => {
-          parsed_args.set(
-            id,
-            matches
-              .get_many::<f64>(id.as_str())
-              .unwrap_or_default()
-              .copied()
-              .collect::<Vec<_>>(),
-          )?;
-        }
-        _ => panic!("Invalid option type: {}", option),
-      },
-      _ => panic!("Unsupported argument action: {:?}", action),
+      _ => {
+        return Err(napi::Error::from_reason(format!(
+          "Unsupported argument action for `{}`: {:?}",
+          id, action
+        )))
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Whether the flag is given in the command line after the other flag
+///
+/// A flag given in the command line beats the other flag which is only set by
+/// the environment variable or the default value.
+fn is_last_occurrence(matches: &clap::ArgMatches, id: &str, other: &str) -> bool {
+  let last_index = |id: &str| match matches.value_source(id) {
+    Some(ValueSource::CommandLine) => matches.indices_of(id).and_then(|indices| indices.max()),
+    _ => None,
+  };
+  match (last_index(id), last_index(other)) {
+    (Some(index), Some(other)) => index > other,
+    (index, _) => index.is_some(),
+  }
+}
+
+/// Prompt for the options of the command which are not given in the command line
+///
+/// The options are prompted in the order of their names, and the prompted
+/// values are returned with the option names.
+fn prompt_missing_options(
+  env: Env,
+  args: &[&clap::Arg],
+  options: &HashMap<String, CommandOption>,
+  matches: &clap::ArgMatches,
+  built: &clap::Command,
+) -> napi::Result<Vec<(String, JsUnknown)>> {
+  let mut names = options
+    .iter()
+    .filter(|(_, opt)| is_promptable(opt))
+    .map(|(name, _)| name)
+    .collect::<Vec<_>>();
+  names.sort();
+
+  let mut prompted = Vec::new();
+  let given = |id: &str| {
+    args.iter().any(|arg| arg.get_id() == id)
+      && matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+      )
+  };
+  let mut present = args
+    .iter()
+    .filter(|arg| given(arg.get_id().as_str()))
+    .map(|arg| arg.get_id().clone())
+    .collect::<Vec<_>>();
+  for name in names {
+    if given(name) || given(&format!("{}{}", NEGATION_ID_PREFIX, name)) {
+      continue;
+    }
+    let Some(arg) = args.iter().find(|arg| arg.get_id() == name.as_str()) else {
+      continue;
+    };
+    // Conflicting options are not required by clap, so they are not prompted
+    if has_conflicts(built, arg, &present) {
+      continue;
     }
+    let flag = matches!(
+      arg.get_action(),
+      clap::ArgAction::SetTrue | clap::ArgAction::SetFalse
+    );
+    let value = prompt_option(env, name, &options[name], flag, is_multiple_values(arg))?;
+    prompted.push((name.clone(), value));
+    present.push(arg.get_id().clone());
+  }
+  Ok(prompted)
+}
+
+/// Whether the argument conflicts with any of the present arguments
+fn has_conflicts(built: &clap::Command, arg: &clap::Arg, present: &[clap::Id]) -> bool {
+  let conflicts = built.get_arg_conflicts_with(arg);
+  present.iter().any(|id| {
+    let Some(other) = built.get_arguments().find(|other| other.get_id() == id) else {
+      return false;
+    };
+    arg.is_exclusive_set()
+      || other.is_exclusive_set()
+      || conflicts.iter().any(|conflict| conflict.get_id() == id)
+      || built
+        .get_arg_conflicts_with(other)
+        .iter()
+        .any(|conflict| conflict.get_id() == arg.get_id())
+  })
+}
+
+fn set_prompted_values(
+  env: Env,
+  parsed_args: &mut JsObject,
+  prompted: &[(String, JsUnknown)],
+) -> napi::Result<()> {
+  for (name, value) in prompted {
+    let (mut namespace, key) = resolve_namespace(env, parsed_args, name)?;
+    let target = match namespace.as_mut() {
+      Some(namespace) => namespace,
+      None => &mut *parsed_args,
+    };
+    target.set_named_property(key, share(env, value))?;
   }
   Ok(())
 }
 
+/// Resolve the binary name from the raw arguments, the same as clap does.
+pub(crate) fn resolve_bin_name(raw_args: &[String]) -> Option<String> {
+  let bin = std::path::Path::new(raw_args.first()?);
+  Some(bin.file_name()?.to_str()?.to_string())
+}
+
+/// Inherit the metadata which applies to the subcommands from the parent command
+fn inherit_meta(meta: &mut CommandMeta, parent: &CommandMeta) {
+  if parent.styled == Some(true) {
+    meta.styled = Some(true);
+  }
+  // The styles of the parent are propagated to the subcommands by clap
+  if parent.theme.is_some() {
+    meta.theme = parent.theme.clone();
+  }
+  if meta.color.is_none() {
+    meta.color = parent.color.clone();
+  }
+  if let Some(parent_exit_codes) = &parent.exit_codes {
+    let mut exit_codes = parent_exit_codes.clone();
+    exit_codes.extend(meta.exit_codes.take().unwrap_or_default());
+    meta.exit_codes = Some(exit_codes);
+  }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn parse_arguments_inner<'arg>(
   env: Env,
   mut parsed_args: JsObject,
   clap: &'arg clap::Command,
-  cmd: Command,
+  mut cmd: Command,
   matches: &clap::ArgMatches,
   raw_args: Vec<String>,
-  mut global_options: HashMap<String, &'static str>,
+  unknown_args: Vec<String>,
+  mut global_options: HashMap<String, CommandOption>,
   mut global_args: Vec<&'arg clap::Arg>,
-) -> napi::Result<()> {
-  let mut options: HashMap<String, &'static str> = HashMap::default();
+  mut hooks: Vec<CommandHooks>,
+  state: JsObject,
+  built: &clap::Command,
+  mut command_path: Vec<String>,
+  parent: Option<(JsObject, CommandMeta)>,
+  interactive: bool,
+) -> napi::Result<JsUnknown> {
+  let mut options: HashMap<String, CommandOption> = HashMap::default();
   options.extend(global_options.clone());
 
   for (name, option) in &cmd.options {
-    let parser = leak_borrowed_str_or_default(option.parser.as_ref(), "string");
-    options.entry(name.to_string()).or_insert(parser);
+    options
+      .entry(name.to_string())
+      .or_insert_with(|| option.clone());
     if option.global.unwrap_or(false) {
-      global_options.entry(name.to_string()).or_insert(parser);
+      global_options
+        .entry(name.to_string())
+        .or_insert_with(|| option.clone());
     }
   }
 
@@ -149,17 +567,86 @@ pub(crate) fn parse_arguments_inner<'arg>(
     .collect::<Vec<&clap::Arg>>();
   global_args.extend(global_args_this);
 
-  merge_args_matches(&mut parsed_args, &args, &options, matches)?;
+  let bin_name = resolve_bin_name(&raw_args).unwrap_or_else(|| clap.get_name().to_string());
+  let mut meta = cmd.meta.clone();
+  let name = match command_path.is_empty() {
+    true => meta.name.clone().unwrap_or_else(|| bin_name.clone()),
+    false => clap.get_name().to_string(),
+  };
+  meta.name = Some(name.clone());
+  if let Some((_, parent_meta)) = &parent {
+    inherit_meta(&mut meta, parent_meta);
+  }
+  apply_prompt_theme(&meta, built);
+
+  if let Some(error) = find_duplicate_map_key(built, &args, &options, matches) {
+    print_clap_error(built, &error)?;
+    return exit_with(env, error.exit_code());
+  }
+  merge_args_matches(env, &mut parsed_args, &args, &options, matches)?;
+  let prompted = match interactive {
+    true => prompt_missing_options(env, &args, &cmd.options, matches, built)?,
+    false => Vec::new(),
+  };
+  set_prompted_values(env, &mut parsed_args, &prompted)?;
+  if let Some(provide) = cmd.provide.take() {
+    hooks.push(resolve_provider(env, provide)?);
+  }
+  hooks.extend(cmd.hooks.take());
+  command_path.push(name);
+  let parent = parent.map(|(parent, _)| parent);
 
   if let Some((sub_command_name, sub_matches)) = matches.subcommand() {
     let mut sub_commands = cmd.subcommands.unwrap_or_default();
-    let sub_command_def = sub_commands.remove(sub_command_name).unwrap();
+    // Subcommands are keyed by their definitions, which can be renamed by `meta.name`
+    let key = sub_commands
+      .iter()
+      .find(|(key, sub_cmd)| sub_cmd.meta.name.as_deref().unwrap_or(key) == sub_command_name)
+      .map(|(key, _)| key.clone())
+      .unwrap();
+    let sub_command_def = sub_commands.remove(&key).unwrap();
 
     let sub_command = clap
       .get_subcommands()
       .find(|&sub_command| sub_command.get_name() == sub_command_name)
       .unwrap();
 
+    // The applets of a multicall command are built as standalone commands,
+    // so that they are displayed by their own names
+    let built_applet;
+    let built_sub_command = match clap.is_multicall_set() {
+      true => {
+        let mut applet = sub_command
+          .clone()
+          .bin_name(sub_command_name)
+          .styles(clap.get_styles().clone());
+        // The version propagated by the build of the multicall command
+        let propagated = built.find_subcommand(sub_command_name);
+        if let Some(version) = propagated.and_then(|applet| applet.get_version()) {
+          applet = applet.version(leak_borrowed_str(version));
+        }
+        if let Some(long_version) = propagated.and_then(|applet| applet.get_long_version()) {
+          applet = applet.long_version(leak_borrowed_str(long_version));
+        }
+        applet.build();
+        built_applet = applet;
+        &built_applet
+      }
+      false => built
+        .find_subcommand(sub_command_name)
+        .expect("Built subcommand not found"),
+    };
+    let mut level_args = env.create_object()?;
+    merge_args_matches(env, &mut level_args, &args, &options, matches)?;
+    set_prompted_values(env, &mut level_args, &prompted)?;
+    let mut context = Context::new(env, level_args, raw_args.clone(), Some(state))?;
+    context.unknown_args = unknown_args.clone();
+    context.command_path = command_path.clone();
+    context.bin_name = bin_name;
+    context.set_command(meta.clone(), built.clone());
+    context.set_parent(env, parent)?;
+    let state = context.state()?;
+
     parse_arguments_inner(
       env,
       parsed_args,
@@ -167,31 +654,58 @@ pub(crate) fn parse_arguments_inner<'arg>(
       sub_command_def,
       sub_matches,
       raw_args,
+      unknown_args,
       global_options,
       global_args,
-    )?;
+      hooks,
+      state,
+      built_sub_command,
+      command_path,
+      Some((Context::into_instance(context, env)?.as_object(env), meta)),
+      interactive,
+    )
   } else {
-    let context = Context::new(parsed_args, raw_args);
-    if let Some(cb) = cmd.callback.as_ref() {
-      cb.call1::<Context, JsNull>(context)?;
+    let mut context = Context::new(env, parsed_args, raw_args, Some(state))?;
+    context.unknown_args = unknown_args;
+    context.command_path = command_path;
+    context.bin_name = bin_name;
+    context.set_command(meta, built.clone());
+    context.set_parent(env, parent)?;
+    if let Some(cb) = cmd.callback {
+      let exit_codes = context.command().exit_codes.unwrap_or_default();
+      let context = Context::into_instance(context, env)?.as_object(env);
+      run_with_hooks(env, context, cb, hooks, exit_codes)
     } else {
       env.throw_error(
         "No callback function found for main command and no subcommand was provided.",
         Some("E_NO_CALLBACK"),
       )?;
-    };
-  };
-  Ok(())
+      Err(napi::Error::new(napi::Status::PendingException, ""))
+    }
+  }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn parse_arguments(
   env: Env,
   clap: &clap::Command,
   cmd: Command,
   matches: &clap::ArgMatches,
   raw_args: Vec<String>,
-) -> napi::Result<()> {
+  unknown_args: Vec<String>,
+  interactive: bool,
+  state: Option<JsObject>,
+) -> napi::Result<JsUnknown> {
   let parsed_args = env.create_object()?;
+  let state = match state {
+    Some(state) => state,
+    None => env.create_object()?,
+  };
+  let mut built = clap.clone();
+  if let Some(bin_name) = resolve_bin_name(&raw_args) {
+    built = built.bin_name(bin_name);
+  }
+  built.build();
 
   parse_arguments_inner(
     env,
@@ -200,7 +714,14 @@ pub(crate) fn parse_arguments(
     cmd,
     matches,
     raw_args,
+    unknown_args,
     HashMap::default(),
     Vec::new(),
+    Vec::new(),
+    state,
+    &built,
+    Vec::new(),
+    None,
+    interactive,
   )
 }